@@ -2,7 +2,8 @@ use std::fs::File;
 use std::path::PathBuf;
 
 use anyhow::Error;
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use json_schema_diff::{Config, Draft, Perspective};
 use serde::Serialize;
 
 /// Compare old and new schema, and print differences
@@ -13,6 +14,56 @@ struct Args {
     lhs: PathBuf,
     /// The new schema
     rhs: PathBuf,
+    /// The draft to assume for schemas that don't declare `$schema`. 2019-09 is the same as
+    /// draft-07 here
+    #[arg(long, value_enum, default_value_t = DraftArg::Draft7)]
+    default_draft: DraftArg,
+    /// Only judge breaking changes from the point of view of producers or consumers of values.
+    /// This affects readOnly/writeOnly changes and, with --defaults-break-consumers, default
+    /// value changes
+    #[arg(long, value_enum)]
+    perspective: Option<PerspectiveArg>,
+    /// Don't report schemas being marked as deprecated or no longer deprecated
+    #[arg(long)]
+    ignore_deprecations: bool,
+    /// Also report changes to title, description, examples and $comment
+    #[arg(long)]
+    diff_annotations: bool,
+    /// Judge adding, removing or changing a default value as breaking for consumers
+    #[arg(long)]
+    defaults_break_consumers: bool,
+}
+
+#[derive(Clone, ValueEnum)]
+enum DraftArg {
+    #[value(name = "draft-07", alias = "2019-09")]
+    Draft7,
+    #[value(name = "2020-12")]
+    Draft2020_12,
+}
+
+impl From<DraftArg> for Draft {
+    fn from(arg: DraftArg) -> Self {
+        match arg {
+            DraftArg::Draft7 => Draft::Draft7,
+            DraftArg::Draft2020_12 => Draft::Draft2020_12,
+        }
+    }
+}
+
+#[derive(Clone, ValueEnum)]
+enum PerspectiveArg {
+    Producer,
+    Consumer,
+}
+
+impl From<PerspectiveArg> for Perspective {
+    fn from(arg: PerspectiveArg) -> Self {
+        match arg {
+            PerspectiveArg::Producer => Perspective::Producer,
+            PerspectiveArg::Consumer => Perspective::Consumer,
+        }
+    }
 }
 
 #[derive(Serialize)]
@@ -28,10 +79,20 @@ fn main() -> Result<(), Error> {
     let lhs: serde_json::Value = serde_json::from_reader(File::open(args.lhs)?)?;
     let rhs: serde_json::Value = serde_json::from_reader(File::open(args.rhs)?)?;
 
-    let changes = json_schema_diff::diff(lhs, rhs)?;
+    let config = Config {
+        default_draft: args.default_draft.into(),
+        ignore_deprecations: args.ignore_deprecations,
+        diff_annotations: args.diff_annotations,
+        defaults_break_consumers: args.defaults_break_consumers,
+    };
+    let changes = json_schema_diff::diff_with_config(lhs, rhs, &config)?;
+    let perspective = args.perspective.map(Perspective::from);
 
     for change in changes {
-        let is_breaking = change.change.is_breaking();
+        let is_breaking = match perspective {
+            Some(perspective) => change.is_breaking_for(perspective),
+            None => change.is_breaking(),
+        };
         let change = Change {
             inner: change,
             is_breaking,
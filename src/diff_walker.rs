@@ -1,79 +1,446 @@
 use std::collections::{BTreeMap, BTreeSet};
 use std::mem::discriminant;
+use std::rc::Rc;
 
 use schemars::schema::{
-    InstanceType, NumberValidation, ObjectValidation, RootSchema, Schema, SchemaObject,
-    SingleOrVec, SubschemaValidation,
+    ArrayValidation, InstanceType, NumberValidation, ObjectValidation, RootSchema, Schema,
+    SchemaObject, SingleOrVec, SubschemaValidation,
 };
 use serde_json::Value;
 
+use crate::merge;
 use crate::resolver::Resolver;
-use crate::{Change, ChangeKind, Error, JsonSchemaType, Range};
+use crate::types::values_equal;
+use crate::{Change, ChangeKind, Config, Draft, Error, JsonSchemaType, Nesting, Range};
 
 pub struct DiffWalker<F: FnMut(Change)> {
     pub cb: F,
-    pub lhs_root: RootSchema,
-    pub rhs_root: RootSchema,
-    lhs_resolver: Resolver,
-    rhs_resolver: Resolver,
+    pub lhs_root: Rc<RootSchema>,
+    pub rhs_root: Rc<RootSchema>,
+    lhs_resolver: Rc<Resolver>,
+    rhs_resolver: Rc<Resolver>,
+    lhs_draft: Draft,
+    rhs_draft: Draft,
+    config: Config,
+    /// How the subschemas that are currently compared are nested within the roots.
+    nesting: Nesting,
 }
 
 impl<F: FnMut(Change)> DiffWalker<F> {
-    pub fn new(cb: F, lhs_root: RootSchema, rhs_root: RootSchema) -> Self {
+    pub fn new(cb: F, lhs_root: RootSchema, rhs_root: RootSchema, config: Config) -> Self {
         let lhs_resolver = Resolver::for_schema(&lhs_root);
         let rhs_resolver = Resolver::for_schema(&rhs_root);
+        let draft = |root: &RootSchema| {
+            root.meta_schema
+                .as_deref()
+                .and_then(Draft::from_meta_schema)
+                .unwrap_or(config.default_draft)
+        };
         Self {
             cb,
-            lhs_root,
-            rhs_root,
-            lhs_resolver,
-            rhs_resolver,
+            lhs_draft: draft(&lhs_root),
+            rhs_draft: draft(&rhs_root),
+            lhs_root: Rc::new(lhs_root),
+            rhs_root: Rc::new(rhs_root),
+            lhs_resolver: Rc::new(lhs_resolver),
+            rhs_resolver: Rc::new(rhs_resolver),
+            config,
+            nesting: Nesting::Plain,
+        }
+    }
+
+    /// A walker over the same roots that reports its changes to `cb`, for diffing subschemas
+    /// without reporting the changes right away.
+    fn sub_walker<'a>(
+        &self,
+        cb: Box<dyn FnMut(Change) + 'a>,
+        config: Config,
+    ) -> DiffWalker<Box<dyn FnMut(Change) + 'a>> {
+        DiffWalker {
+            cb,
+            lhs_root: Rc::clone(&self.lhs_root),
+            rhs_root: Rc::clone(&self.rhs_root),
+            lhs_resolver: Rc::clone(&self.lhs_resolver),
+            rhs_resolver: Rc::clone(&self.rhs_resolver),
+            lhs_draft: self.lhs_draft,
+            rhs_draft: self.rhs_draft,
+            config,
+            nesting: Nesting::Plain,
+        }
+    }
+
+    /// Pair up the subschemas of `lhs` and `rhs` such that the total number of changes between
+    /// paired subschemas is minimal. Returns, for each index in `lhs`, the index in `rhs`.
+    fn match_subschemas(
+        &self,
+        lhs: &mut [Schema],
+        rhs: &mut [Schema],
+    ) -> Result<Vec<usize>, Error> {
+        let mut mat = pathfinding::matrix::Matrix::new(lhs.len(), rhs.len(), 0i32);
+        for (i, l) in lhs.iter_mut().enumerate() {
+            for (j, r) in rhs.iter_mut().enumerate() {
+                let mut count = 0;
+                // Annotations shouldn't influence which subschemas are paired up.
+                let counter = |change: Change| {
+                    if change.change.affects_validation() {
+                        count += 1;
+                    }
+                };
+                self.sub_walker(
+                    Box::new(counter),
+                    Config {
+                        ignore_deprecations: true,
+                        diff_annotations: false,
+                        ..self.config.clone()
+                    },
+                )
+                .diff("", l, r)?;
+                mat[(i, j)] = count;
+            }
+        }
+        Ok(pathfinding::kuhn_munkres::kuhn_munkres_min(&mat).1)
+    }
+
+    /// Diff `lhs` against `rhs` without reporting the changes. The paths of the returned changes
+    /// are relative, so that [`Change::is_breaking`] can judge them on their own.
+    fn collect_changes(&self, lhs: &mut Schema, rhs: &mut Schema) -> Result<Vec<Change>, Error> {
+        let mut changes = vec![];
+        self.sub_walker(
+            Box::new(|change: Change| changes.push(change)),
+            self.config.clone(),
+        )
+        .diff("", lhs, rhs)?;
+        Ok(changes)
+    }
+
+    /// Report changes returned by [`Self::collect_changes`] below `json_path`.
+    fn emit_nested(&mut self, json_path: &str, changes: Vec<Change>) {
+        for change in changes {
+            (self.cb)(Change {
+                path: format!("{json_path}{}", change.path),
+                nesting: self.nesting.nest(change.nesting),
+                change: change.change,
+            });
         }
     }
 
+    /// Diff subschemas that are nested like `nesting` within the current ones, such as the
+    /// subschemas of `not`.
+    fn diff_nested(
+        &mut self,
+        json_path: &str,
+        nesting: Nesting,
+        lhs: &mut Schema,
+        rhs: &mut Schema,
+    ) -> Result<(), Error> {
+        let outer = self.nesting;
+        self.nesting = outer.nest(nesting);
+        let result = self.diff(json_path, lhs, rhs);
+        self.nesting = outer;
+        result
+    }
+
     fn diff_any_of(
         &mut self,
         json_path: &str,
+        is_lhs_split: bool,
         is_rhs_split: bool,
         lhs: &mut SchemaObject,
         rhs: &mut SchemaObject,
     ) -> Result<(), Error> {
+        let mut lhs_compositions = Composition::of_schema(lhs);
+        let mut rhs_compositions = Composition::of_schema(rhs);
+
+        // A schema that was split by type is compared variant by variant, so compare a schema
+        // with a single type on the other side as if it had been split as well.
+        if is_rhs_split && lhs_compositions.is_empty() {
+            lhs_compositions.extend(Self::single_type_variant(lhs));
+        }
+        if is_lhs_split && rhs_compositions.is_empty() {
+            rhs_compositions.extend(Self::single_type_variant(rhs));
+        }
+
+        // Only compare anyOf with oneOf if there is no better match on the other side.
+        if let ([lhs_composition], [rhs_composition]) =
+            (lhs_compositions.as_slice(), rhs_compositions.as_slice())
+        {
+            return self.diff_variants(json_path, is_rhs_split, lhs_composition, rhs_composition);
+        }
+
+        for lhs_composition in &lhs_compositions {
+            if let Some(rhs_composition) = rhs_compositions
+                .iter()
+                .find(|(kind, _)| *kind == lhs_composition.0)
+            {
+                self.diff_variants(json_path, is_rhs_split, lhs_composition, rhs_composition)?;
+            } else if !is_lhs_split && !only_restricts_type(lhs, lhs_composition) {
+                let (lhs_kind, lhs_variants) = lhs_composition;
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: lhs_kind.removed(),
+                });
+
+                // Without the composition, values only have to match the rest of the schema.
+                for (i, lhs_variant) in lhs_variants.iter().enumerate() {
+                    let new_path = format!("{json_path}.<{}:{i}>", lhs_kind.keyword());
+                    self.diff(&new_path, &mut lhs_variant.clone(), &mut Schema::Bool(true))?;
+                }
+            }
+        }
+
+        for (rhs_kind, rhs_variants) in &rhs_compositions {
+            if !is_rhs_split
+                && !only_restricts_type(rhs, &(*rhs_kind, rhs_variants.clone()))
+                && !lhs_compositions.iter().any(|(kind, _)| kind == rhs_kind)
+            {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: rhs_kind.added(),
+                });
+
+                for (i, rhs_variant) in rhs_variants.iter().enumerate() {
+                    let new_path = format!("{json_path}.<{}:{i}>", rhs_kind.keyword());
+                    self.diff(&new_path, &mut Schema::Bool(true), &mut rhs_variant.clone())?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn diff_variants(
+        &mut self,
+        json_path: &str,
+        is_rhs_split: bool,
+        (lhs_kind, lhs_variants): &(Composition, Vec<Schema>),
+        (rhs_kind, rhs_variants): &(Composition, Vec<Schema>),
+    ) -> Result<(), Error> {
+        let lhs_len = lhs_variants.len();
+        let rhs_len = rhs_variants.len();
+
         // hack to get a stable order for anyOf. serde_json::Value does not impl Hash or Ord, so we
         // can't use a set.
-        if let (Some(lhs_any_of), Some(rhs_any_of)) =
-            (&mut lhs.subschemas().any_of, &mut rhs.subschemas().any_of)
-        {
-            let max_len = lhs_any_of.len().max(rhs_any_of.len());
-            lhs_any_of.resize(max_len, Schema::Bool(false));
-            rhs_any_of.resize(max_len, Schema::Bool(false));
-
-            let mut mat = pathfinding::matrix::Matrix::new(max_len, max_len, 0i32);
-            for (i, l) in lhs_any_of.iter_mut().enumerate() {
-                for (j, r) in rhs_any_of.iter_mut().enumerate() {
-                    let mut count = 0;
-                    let counter = |_change: Change| count += 1;
-                    DiffWalker::new(
-                        Box::new(counter) as Box<dyn FnMut(Change)>,
-                        self.lhs_root.clone(),
-                        self.rhs_root.clone(),
-                    )
-                    .diff("", l, r)?;
-                    mat[(i, j)] = count;
+        let max_len = lhs_len.max(rhs_len);
+        let mut lhs_variants = lhs_variants.clone();
+        let mut rhs_variants = rhs_variants.clone();
+        lhs_variants.resize(max_len, Schema::Bool(false));
+        rhs_variants.resize(max_len, Schema::Bool(false));
+
+        let pairs = self.match_subschemas(&mut lhs_variants, &mut rhs_variants)?;
+
+        if *lhs_kind == Composition::OneOf || *rhs_kind == Composition::OneOf {
+            let lhs_overlaps = overlapping_variants(&lhs_variants, &|reference| {
+                self.lhs_resolver
+                    .resolve(&self.lhs_root, reference)
+                    .cloned()
+            });
+            let rhs_overlaps = overlapping_variants(&rhs_variants, &|reference| {
+                self.rhs_resolver
+                    .resolve(&self.rhs_root, reference)
+                    .cloned()
+            });
+
+            match (lhs_kind, rhs_kind) {
+                (Composition::AnyOf, Composition::OneOf) if !rhs_overlaps.is_empty() => {
+                    (self.cb)(Change {
+                        path: json_path.to_owned(),
+                        nesting: self.nesting,
+                        change: ChangeKind::AnyOfToOneOf,
+                    });
+                }
+                (Composition::OneOf, Composition::AnyOf) if !lhs_overlaps.is_empty() => {
+                    (self.cb)(Change {
+                        path: json_path.to_owned(),
+                        nesting: self.nesting,
+                        change: ChangeKind::OneOfToAnyOf,
+                    });
+                }
+                (Composition::OneOf, Composition::OneOf) => {
+                    // Translate to rhs indices, so that overlaps can be compared across sides.
+                    let lhs_overlaps: BTreeSet<_> = lhs_overlaps
+                        .into_iter()
+                        .map(|(i, j)| (pairs[i].min(pairs[j]), pairs[i].max(pairs[j])))
+                        .collect();
+
+                    for &variants in lhs_overlaps.difference(&rhs_overlaps) {
+                        // Overlaps with removed variants are gone, but that is already reported
+                        // by the diff of the removed variant itself.
+                        if variants.1 < rhs_len {
+                            (self.cb)(Change {
+                                path: json_path.to_owned(),
+                                nesting: self.nesting,
+                                change: ChangeKind::OneOfOverlapRemove { variants },
+                            });
+                        }
+                    }
+
+                    for &variants in rhs_overlaps.difference(&lhs_overlaps) {
+                        (self.cb)(Change {
+                            path: json_path.to_owned(),
+                            nesting: self.nesting,
+                            change: ChangeKind::OneOfOverlapAdd { variants },
+                        });
+                    }
                 }
+                _ => (),
             }
-            let pairs = pathfinding::kuhn_munkres::kuhn_munkres_min(&mat).1;
-            for i in 0..max_len {
-                let new_path = match is_rhs_split {
-                    true => json_path.to_owned(),
-                    false => format!("{json_path}.<anyOf:{}>", pairs[i]),
-                };
-                self.do_diff(
-                    &new_path,
-                    true,
-                    &mut lhs_any_of[i].clone().into_object(),
-                    &mut rhs_any_of[pairs[i]].clone().into_object(),
-                )?;
+        }
+
+        for i in 0..max_len {
+            let new_path = match is_rhs_split {
+                true => json_path.to_owned(),
+                false => format!("{json_path}.<{}:{}>", rhs_kind.keyword(), pairs[i]),
+            };
+            self.do_diff(
+                &new_path,
+                true,
+                &mut lhs_variants[i].clone().into_object(),
+                &mut rhs_variants[pairs[i]].clone().into_object(),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Diff the `allOf` branches that could not be merged into their parent schema by
+    /// `Self::merge_all_of`. Merged branches are left in place as `true`, so the paths refer to
+    /// the index of the branch in the original `allOf`.
+    fn diff_all_of(
+        &mut self,
+        json_path: &str,
+        lhs: &mut SchemaObject,
+        rhs: &mut SchemaObject,
+    ) -> Result<(), Error> {
+        let mut lhs_all_of = lhs.subschemas().all_of.clone().unwrap_or_default();
+        let mut rhs_all_of = rhs.subschemas().all_of.clone().unwrap_or_default();
+        if lhs_all_of.is_empty() && rhs_all_of.is_empty() {
+            return Ok(());
+        }
+
+        // A missing branch does not constrain anything.
+        let max_len = lhs_all_of.len().max(rhs_all_of.len());
+        lhs_all_of.resize(max_len, Schema::Bool(true));
+        rhs_all_of.resize(max_len, Schema::Bool(true));
+
+        let pairs = self.match_subschemas(&mut lhs_all_of, &mut rhs_all_of)?;
+        for i in 0..max_len {
+            let new_path = format!("{json_path}.<allOf:{}>", pairs[i]);
+            self.diff(&new_path, &mut lhs_all_of[i], &mut rhs_all_of[pairs[i]])?;
+        }
+
+        Ok(())
+    }
+
+    fn diff_not(
+        &mut self,
+        json_path: &str,
+        lhs: &mut SchemaObject,
+        rhs: &mut SchemaObject,
+    ) -> Result<(), Error> {
+        // `{"not": {}}` is already reported as a change of the effective type.
+        let not_schema = |schema: &mut SchemaObject| {
+            schema
+                .subschemas()
+                .not
+                .clone()
+                .filter(|not| !not.clone().into_object().is_true())
+        };
+
+        match (not_schema(lhs), not_schema(rhs)) {
+            (Some(mut lhs_not), Some(mut rhs_not)) => {
+                let new_path = format!("{json_path}.<not>");
+                self.diff_nested(&new_path, Nesting::Negated, &mut lhs_not, &mut rhs_not)?;
+            }
+            (Some(_), None) => (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::NotRemove,
+            }),
+            (None, Some(_)) => (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::NotAdd,
+            }),
+            (None, None) => (),
+        }
+
+        Ok(())
+    }
+
+    fn diff_conditional(
+        &mut self,
+        json_path: &str,
+        lhs: &mut SchemaObject,
+        rhs: &mut SchemaObject,
+    ) -> Result<(), Error> {
+        let lhs_sub = lhs.subschemas();
+        let rhs_sub = rhs.subschemas();
+
+        // `then` and `else` have no effect without `if`.
+        let has_branches =
+            |sub: &SubschemaValidation| sub.then_schema.is_some() || sub.else_schema.is_some();
+        let lhs_has_branches = has_branches(lhs_sub);
+        let rhs_has_branches = has_branches(rhs_sub);
+        match (&mut lhs_sub.if_schema, &mut rhs_sub.if_schema) {
+            (Some(_), None) if lhs_has_branches => {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: ChangeKind::ConditionalRemove,
+                });
+                return Ok(());
             }
+            (None, Some(_)) if rhs_has_branches => {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: ChangeKind::ConditionalAdd,
+                });
+                return Ok(());
+            }
+            (Some(lhs_if), Some(rhs_if)) => {
+                let new_path = format!("{json_path}.<if>");
+                self.diff_nested(&new_path, Nesting::Condition, lhs_if, rhs_if)?;
+            }
+            _ => return Ok(()),
+        }
+
+        match (&mut lhs_sub.then_schema, &mut rhs_sub.then_schema) {
+            (Some(lhs_then), Some(rhs_then)) => {
+                let new_path = format!("{json_path}.<then>");
+                self.diff(&new_path, lhs_then, rhs_then)?;
+            }
+            (Some(_), None) => (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::ThenRemove,
+            }),
+            (None, Some(_)) => (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::ThenAdd,
+            }),
+            (None, None) => (),
+        }
+
+        match (&mut lhs_sub.else_schema, &mut rhs_sub.else_schema) {
+            (Some(lhs_else), Some(rhs_else)) => {
+                let new_path = format!("{json_path}.<else>");
+                self.diff(&new_path, lhs_else, rhs_else)?;
+            }
+            (Some(_), None) => (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::ElseRemove,
+            }),
+            (None, Some(_)) => (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::ElseAdd,
+            }),
+            (None, None) => (),
         }
 
         Ok(())
@@ -91,6 +458,7 @@ impl<F: FnMut(Change)> DiffWalker<F> {
         for removed in lhs_ty.difference(&rhs_ty) {
             (self.cb)(Change {
                 path: json_path.to_owned(),
+                nesting: self.nesting,
                 change: ChangeKind::TypeRemove {
                     removed: removed.clone(),
                 },
@@ -100,6 +468,7 @@ impl<F: FnMut(Change)> DiffWalker<F> {
         for added in rhs_ty.difference(&lhs_ty) {
             (self.cb)(Change {
                 path: json_path.to_owned(),
+                nesting: self.nesting,
                 change: ChangeKind::TypeAdd {
                     added: added.clone(),
                 },
@@ -108,28 +477,36 @@ impl<F: FnMut(Change)> DiffWalker<F> {
     }
 
     fn diff_const(&mut self, json_path: &str, lhs: &mut SchemaObject, rhs: &mut SchemaObject) {
-        Self::normalize_const(lhs);
-        Self::normalize_const(rhs);
+        let is_const_array =
+            |schema: &SchemaObject| matches!(schema.const_value, Some(Value::Array(_)));
+        let expand_arrays = is_const_array(lhs) && is_const_array(rhs);
+        Self::normalize_const(lhs, expand_arrays);
+        Self::normalize_const(rhs, expand_arrays);
+
         match (&lhs.const_value, &rhs.const_value) {
             (Some(value), None) => (self.cb)(Change {
                 path: json_path.to_owned(),
+                nesting: self.nesting,
                 change: ChangeKind::ConstRemove {
                     removed: value.clone(),
                 },
             }),
             (None, Some(value)) => (self.cb)(Change {
                 path: json_path.to_owned(),
+                nesting: self.nesting,
                 change: ChangeKind::ConstAdd {
                     added: value.clone(),
                 },
             }),
-            (Some(l), Some(r)) if l != r => {
+            (Some(l), Some(r)) if !values_equal(l, r) => {
                 (self.cb)(Change {
                     path: json_path.to_owned(),
+                    nesting: self.nesting,
                     change: ChangeKind::ConstRemove { removed: l.clone() },
                 });
                 (self.cb)(Change {
                     path: json_path.to_owned(),
+                    nesting: self.nesting,
                     change: ChangeKind::ConstAdd { added: r.clone() },
                 });
             }
@@ -146,30 +523,52 @@ impl<F: FnMut(Change)> DiffWalker<F> {
         let lhs_props: BTreeSet<_> = lhs.object().properties.keys().cloned().collect();
         let rhs_props: BTreeSet<_> = rhs.object().properties.keys().cloned().collect();
 
-        let lhs_additional_properties = lhs
-            .object()
-            .additional_properties
-            .as_ref()
-            .is_none_or(|x| x.clone().into_object().is_true());
-
         for removed in lhs_props.difference(&rhs_props) {
+            // If the property now falls through to a schema such as a typed additionalProperties,
+            // judge the removal by what that schema accepts of the old values.
+            let mut lhs_child = lhs.object().properties[removed.as_str()].clone();
+            let (lhs_additional_properties, changes) =
+                match Self::undeclared_property_schema(rhs, removed)? {
+                    Some(mut rhs_child) if !is_trivial(&rhs_child) => {
+                        let changes = self.collect_changes(&mut lhs_child, &mut rhs_child)?;
+                        (!changes.iter().any(Change::is_breaking), changes)
+                    }
+                    // It's unknown what validates the property now, which might not allow it.
+                    None => (false, vec![]),
+                    Some(rhs_child) => (rhs_child.into_object().is_true(), vec![]),
+                };
+
             (self.cb)(Change {
                 path: json_path.to_owned(),
+                nesting: self.nesting,
                 change: ChangeKind::PropertyRemove {
                     lhs_additional_properties,
                     removed: removed.clone(),
                 },
             });
+            self.emit_nested(&format!("{json_path}.{removed}"), changes);
         }
 
         for added in rhs_props.difference(&lhs_props) {
+            let mut rhs_child = rhs.object().properties[added.as_str()].clone();
+            let (lhs_additional_properties, changes) =
+                match Self::undeclared_property_schema(lhs, added)? {
+                    Some(mut lhs_child) if !is_trivial(&lhs_child) => {
+                        let changes = self.collect_changes(&mut lhs_child, &mut rhs_child)?;
+                        (changes.iter().any(Change::is_breaking), changes)
+                    }
+                    lhs_child => (allows_any(lhs_child), vec![]),
+                };
+
             (self.cb)(Change {
                 path: json_path.to_owned(),
+                nesting: self.nesting,
                 change: ChangeKind::PropertyAdd {
                     lhs_additional_properties,
                     added: added.clone(),
                 },
             });
+            self.emit_nested(&format!("{json_path}.{added}"), changes);
         }
 
         for common in rhs_props.intersection(&lhs_props) {
@@ -183,25 +582,170 @@ impl<F: FnMut(Change)> DiffWalker<F> {
         Ok(())
     }
 
+    fn diff_pattern_properties(
+        &mut self,
+        json_path: &str,
+        lhs: &mut SchemaObject,
+        rhs: &mut SchemaObject,
+    ) -> Result<(), Error> {
+        let lhs_patterns: BTreeSet<_> = lhs.object().pattern_properties.keys().cloned().collect();
+        let rhs_patterns: BTreeSet<_> = rhs.object().pattern_properties.keys().cloned().collect();
+
+        // Judge a pattern that is added or removed by how it compares to the schema that
+        // validates matching properties on the other side. Only report the changes within that
+        // schema if it isn't trivial, like for properties.
+        let lhs_unmatched = Self::unmatched_property_schema(lhs)?;
+        let rhs_unmatched = Self::unmatched_property_schema(rhs)?;
+
+        for removed in lhs_patterns.difference(&rhs_patterns) {
+            let mut lhs_child = lhs.object().pattern_properties[removed.as_str()].clone();
+            let changes = self.collect_changes(&mut lhs_child, &mut rhs_unmatched.clone())?;
+
+            (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::PatternPropertyRemove {
+                    restricts: changes.iter().any(Change::is_breaking),
+                    removed: removed.clone(),
+                },
+            });
+            if !is_trivial(&rhs_unmatched) {
+                self.emit_nested(
+                    &format!("{json_path}.<patternProperties:{removed}>"),
+                    changes,
+                );
+            }
+        }
+
+        for added in rhs_patterns.difference(&lhs_patterns) {
+            let mut rhs_child = rhs.object().pattern_properties[added.as_str()].clone();
+            let changes = self.collect_changes(&mut lhs_unmatched.clone(), &mut rhs_child)?;
+
+            (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::PatternPropertyAdd {
+                    restricts: changes.iter().any(Change::is_breaking),
+                    added: added.clone(),
+                },
+            });
+            if !is_trivial(&lhs_unmatched) {
+                self.emit_nested(&format!("{json_path}.<patternProperties:{added}>"), changes);
+            }
+        }
+
+        for common in rhs_patterns.intersection(&lhs_patterns) {
+            let lhs_child = lhs
+                .object()
+                .pattern_properties
+                .get_mut(common.as_str())
+                .unwrap();
+            let rhs_child = rhs
+                .object()
+                .pattern_properties
+                .get_mut(common.as_str())
+                .unwrap();
+
+            let new_path = format!("{json_path}.<patternProperties:{common}>");
+            self.diff(&new_path, lhs_child, rhs_child)?;
+        }
+
+        Ok(())
+    }
+
     fn diff_additional_properties(
         &mut self,
         json_path: &str,
         lhs: &mut SchemaObject,
         rhs: &mut SchemaObject,
     ) -> Result<(), Error> {
-        if let (Some(lhs_additional_properties), Some(rhs_additional_properties)) = (
-            &mut lhs.object().additional_properties,
-            &mut rhs.object().additional_properties,
-        ) {
-            if rhs_additional_properties != lhs_additional_properties {
-                let new_path = format!("{json_path}.<additionalProperties>");
+        // A missing additionalProperties allows anything.
+        let additional_properties = |schema: &mut SchemaObject| {
+            schema
+                .object()
+                .additional_properties
+                .as_deref()
+                .cloned()
+                .unwrap_or(Schema::Bool(true))
+        };
+        let mut lhs_additional_properties = additional_properties(lhs);
+        let mut rhs_additional_properties = additional_properties(rhs);
+        if lhs_additional_properties == rhs_additional_properties {
+            return Ok(());
+        }
 
-                self.diff(
-                    &new_path,
-                    lhs_additional_properties,
-                    rhs_additional_properties,
-                )?;
+        let changes = self.collect_changes(
+            &mut lhs_additional_properties,
+            &mut rhs_additional_properties,
+        )?;
+
+        // Summarize the changes within additionalProperties, so that it is clear whether
+        // properties that aren't declared explicitly are affected for the worse.
+        if !changes.is_empty() {
+            let change = match changes.iter().any(Change::is_breaking) {
+                true => ChangeKind::AdditionalPropertiesRestrict,
+                false => ChangeKind::AdditionalPropertiesRelax,
+            };
+            (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change,
+            });
+        }
+
+        self.emit_nested(&format!("{json_path}.<additionalProperties>"), changes);
+
+        Ok(())
+    }
+
+    fn diff_property_names(
+        &mut self,
+        json_path: &str,
+        lhs: &mut SchemaObject,
+        rhs: &mut SchemaObject,
+    ) -> Result<(), Error> {
+        // Property names are always strings, so don't report a type change for a schema that
+        // doesn't declare its type.
+        let property_names = |schema: &mut SchemaObject| {
+            let mut names = schema
+                .object()
+                .property_names
+                .as_deref()
+                .cloned()
+                .unwrap_or(Schema::Bool(true))
+                .into_object();
+            if names.effective_type() == InternalJsonSchemaType::Any {
+                names.instance_type = Some(InstanceType::String.into());
             }
+            Schema::Object(names)
+        };
+
+        let mut lhs_names = property_names(lhs);
+        let mut rhs_names = property_names(rhs);
+        if lhs_names != rhs_names {
+            let new_path = format!("{json_path}.<propertyNames>");
+            self.diff(&new_path, &mut lhs_names, &mut rhs_names)?;
+        }
+
+        Ok(())
+    }
+
+    /// Diff `unevaluatedProperties` or `unevaluatedItems`, depending on `keyword`.
+    fn diff_unevaluated(
+        &mut self,
+        json_path: &str,
+        keyword: &str,
+        lhs: &mut SchemaObject,
+        rhs: &mut SchemaObject,
+    ) -> Result<(), Error> {
+        let mut lhs_unevaluated =
+            Self::extension_schema(lhs, keyword)?.unwrap_or(Schema::Bool(true));
+        let mut rhs_unevaluated =
+            Self::extension_schema(rhs, keyword)?.unwrap_or(Schema::Bool(true));
+
+        if lhs_unevaluated != rhs_unevaluated {
+            let new_path = format!("{json_path}.<{keyword}>");
+            self.diff(&new_path, &mut lhs_unevaluated, &mut rhs_unevaluated)?;
         }
 
         Ok(())
@@ -216,10 +760,12 @@ impl<F: FnMut(Change)> DiffWalker<F> {
         let mut diff = |lhs, rhs| match (lhs, rhs) {
             (None, Some(value)) => (self.cb)(Change {
                 path: json_path.to_owned(),
+                nesting: self.nesting,
                 change: ChangeKind::RangeAdd { added: value },
             }),
             (Some(value), None) => (self.cb)(Change {
                 path: json_path.to_owned(),
+                nesting: self.nesting,
                 change: ChangeKind::RangeRemove { removed: value },
             }),
             (Some(lhs), Some(rhs))
@@ -228,6 +774,7 @@ impl<F: FnMut(Change)> DiffWalker<F> {
             {
                 (self.cb)(Change {
                     path: json_path.to_owned(),
+                    nesting: self.nesting,
                     change: ChangeKind::RangeChange {
                         old_value: lhs,
                         new_value: rhs,
@@ -261,66 +808,158 @@ impl<F: FnMut(Change)> DiffWalker<F> {
         Ok(())
     }
 
-    fn diff_array_items(
+    fn diff_multiple_of(
         &mut self,
         json_path: &str,
         lhs: &mut SchemaObject,
         rhs: &mut SchemaObject,
-    ) -> Result<(), Error> {
-        match (&mut lhs.array().items, &mut rhs.array().items) {
-            (Some(SingleOrVec::Vec(lhs_items)), Some(SingleOrVec::Vec(rhs_items))) => {
-                if lhs_items.len() != rhs_items.len() {
-                    (self.cb)(Change {
-                        path: json_path.to_owned(),
-                        change: ChangeKind::TupleChange {
-                            new_length: rhs_items.len(),
-                        },
-                    });
-                }
+    ) {
+        let lhs_multiple_of = lhs.number_validation().multiple_of;
+        let rhs_multiple_of = rhs.number_validation().multiple_of;
 
-                for (i, (lhs_inner, rhs_inner)) in
-                    lhs_items.iter_mut().zip(rhs_items.iter_mut()).enumerate()
-                {
-                    let new_path = format!("{json_path}.{i}");
-                    self.diff(&new_path, lhs_inner, rhs_inner)?;
-                }
-            }
-            (Some(SingleOrVec::Single(lhs_inner)), Some(SingleOrVec::Single(rhs_inner))) => {
-                let new_path = format!("{json_path}.?");
-                self.diff(&new_path, lhs_inner, rhs_inner)?;
-            }
-            (Some(SingleOrVec::Single(lhs_inner)), Some(SingleOrVec::Vec(rhs_items))) => {
+        match (lhs_multiple_of, rhs_multiple_of) {
+            (Some(lhs_val), Some(rhs_val)) if lhs_val != rhs_val => {
                 (self.cb)(Change {
                     path: json_path.to_owned(),
-                    change: ChangeKind::ArrayToTuple {
-                        new_length: rhs_items.len(),
+                    nesting: self.nesting,
+                    change: ChangeKind::MultipleOfChange {
+                        old_value: lhs_val,
+                        new_value: rhs_val,
                     },
                 });
-
-                for (i, rhs_inner) in rhs_items.iter_mut().enumerate() {
-                    let new_path = format!("{json_path}.{i}");
-                    self.diff(&new_path, lhs_inner, rhs_inner)?;
-                }
             }
-            (Some(SingleOrVec::Vec(lhs_items)), Some(SingleOrVec::Single(rhs_inner))) => {
+            (Some(removed_val), None) => {
                 (self.cb)(Change {
                     path: json_path.to_owned(),
-                    change: ChangeKind::TupleToArray {
-                        old_length: lhs_items.len(),
+                    nesting: self.nesting,
+                    change: ChangeKind::MultipleOfRemove {
+                        removed: removed_val,
                     },
                 });
-
-                for (i, lhs_inner) in lhs_items.iter_mut().enumerate() {
-                    let new_path = format!("{json_path}.{i}");
+            }
+            (None, Some(added_val)) => {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: ChangeKind::MultipleOfAdd { added: added_val },
+                });
+            }
+            _ => {} // No change or both None
+        }
+    }
+
+    fn diff_array_items(
+        &mut self,
+        json_path: &str,
+        lhs: &mut SchemaObject,
+        rhs: &mut SchemaObject,
+    ) -> Result<(), Error> {
+        let additional_items = |schema: &mut SchemaObject| {
+            schema
+                .array()
+                .additional_items
+                .as_deref()
+                .cloned()
+                .unwrap_or(Schema::Bool(true))
+        };
+        let mut lhs_additional_items = additional_items(lhs);
+        let mut rhs_additional_items = additional_items(rhs);
+
+        if lhs.array().items.is_none() && rhs.array().items.is_none() {
+            return Ok(());
+        }
+
+        // A missing items allows any item.
+        let items = |schema: &mut SchemaObject| {
+            schema
+                .array()
+                .items
+                .clone()
+                .unwrap_or_else(|| SingleOrVec::Single(Box::new(Schema::Bool(true))))
+        };
+
+        // Items beyond the end of a tuple are validated by its additionalItems, which is called
+        // `items` in 2020-12.
+        let rest_path = match self.rhs_draft {
+            Draft::Draft2020_12 => format!("{json_path}.<items>"),
+            Draft::Draft7 => format!("{json_path}.<additionalItems>"),
+        };
+
+        match (&mut items(lhs), &mut items(rhs)) {
+            (SingleOrVec::Vec(lhs_items), SingleOrVec::Vec(rhs_items)) => {
+                if lhs_items.len() != rhs_items.len() {
+                    (self.cb)(Change {
+                        path: json_path.to_owned(),
+                        nesting: self.nesting,
+                        change: ChangeKind::TupleChange {
+                            new_length: rhs_items.len(),
+                        },
+                    });
+                }
+
+                for (i, (lhs_inner, rhs_inner)) in
+                    lhs_items.iter_mut().zip(rhs_items.iter_mut()).enumerate()
+                {
+                    let new_path = format!("{json_path}.{i}");
                     self.diff(&new_path, lhs_inner, rhs_inner)?;
                 }
+
+                for (i, lhs_inner) in lhs_items.iter_mut().enumerate().skip(rhs_items.len()) {
+                    let new_path = format!("{json_path}.{i}");
+                    self.diff(&new_path, lhs_inner, &mut rhs_additional_items.clone())?;
+                }
+
+                for (i, rhs_inner) in rhs_items.iter_mut().enumerate().skip(lhs_items.len()) {
+                    let new_path = format!("{json_path}.{i}");
+                    self.diff(&new_path, &mut lhs_additional_items.clone(), rhs_inner)?;
+                }
+
+                if lhs_additional_items != rhs_additional_items {
+                    self.diff(
+                        &rest_path,
+                        &mut lhs_additional_items,
+                        &mut rhs_additional_items,
+                    )?;
+                }
             }
-            (None, None) => (),
+            (SingleOrVec::Single(lhs_inner), SingleOrVec::Single(rhs_inner)) => {
+                let new_path = format!("{json_path}.?");
+                self.diff(&new_path, lhs_inner, rhs_inner)?;
+            }
+            (SingleOrVec::Single(lhs_inner), SingleOrVec::Vec(rhs_items)) => {
+                if !tuple_is_array(rhs_items, &rhs_additional_items, lhs_inner) {
+                    (self.cb)(Change {
+                        path: json_path.to_owned(),
+                        nesting: self.nesting,
+                        change: ChangeKind::ArrayToTuple {
+                            new_length: rhs_items.len(),
+                        },
+                    });
+                }
 
-            #[cfg(not(test))]
-            _ => (),
-            #[cfg(test)]
-            (x, y) => todo!("{:?} {:?}", x, y),
+                for (i, rhs_inner) in rhs_items.iter_mut().enumerate() {
+                    let new_path = format!("{json_path}.{i}");
+                    self.diff(&new_path, lhs_inner, rhs_inner)?;
+                }
+                self.diff(&rest_path, lhs_inner, &mut rhs_additional_items)?;
+            }
+            (SingleOrVec::Vec(lhs_items), SingleOrVec::Single(rhs_inner)) => {
+                if !tuple_is_array(lhs_items, &lhs_additional_items, rhs_inner) {
+                    (self.cb)(Change {
+                        path: json_path.to_owned(),
+                        nesting: self.nesting,
+                        change: ChangeKind::TupleToArray {
+                            old_length: lhs_items.len(),
+                        },
+                    });
+                }
+
+                for (i, lhs_inner) in lhs_items.iter_mut().enumerate() {
+                    let new_path = format!("{json_path}.{i}");
+                    self.diff(&new_path, lhs_inner, rhs_inner)?;
+                }
+                self.diff(&rest_path, &mut lhs_additional_items, rhs_inner)?;
+            }
         }
 
         Ok(())
@@ -338,6 +977,7 @@ impl<F: FnMut(Change)> DiffWalker<F> {
         for removed in lhs_required.difference(rhs_required) {
             (self.cb)(Change {
                 path: json_path.to_owned(),
+                nesting: self.nesting,
                 change: ChangeKind::RequiredRemove {
                     property: removed.clone(),
                 },
@@ -347,6 +987,7 @@ impl<F: FnMut(Change)> DiffWalker<F> {
         for added in rhs_required.difference(lhs_required) {
             (self.cb)(Change {
                 path: json_path.to_owned(),
+                nesting: self.nesting,
                 change: ChangeKind::RequiredAdd {
                     property: added.clone(),
                 },
@@ -356,11 +997,90 @@ impl<F: FnMut(Change)> DiffWalker<F> {
         Ok(())
     }
 
+    fn diff_dependencies(
+        &mut self,
+        json_path: &str,
+        lhs: &mut SchemaObject,
+        rhs: &mut SchemaObject,
+    ) -> Result<(), Error> {
+        let Dependencies {
+            required: lhs_required,
+            schemas: mut lhs_schemas,
+        } = Self::dependencies(lhs)?;
+        let Dependencies {
+            required: rhs_required,
+            schemas: mut rhs_schemas,
+        } = Self::dependencies(rhs)?;
+
+        let no_dependents = BTreeSet::new();
+        let triggers: BTreeSet<_> = lhs_required.keys().chain(rhs_required.keys()).collect();
+        for property in triggers {
+            let lhs_dependents = lhs_required.get(property).unwrap_or(&no_dependents);
+            let rhs_dependents = rhs_required.get(property).unwrap_or(&no_dependents);
+
+            for removed in lhs_dependents.difference(rhs_dependents) {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: ChangeKind::DependentRequiredRemove {
+                        property: property.clone(),
+                        dependent: removed.clone(),
+                    },
+                });
+            }
+
+            for added in rhs_dependents.difference(lhs_dependents) {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: ChangeKind::DependentRequiredAdd {
+                        property: property.clone(),
+                        dependent: added.clone(),
+                    },
+                });
+            }
+        }
+
+        let lhs_props: BTreeSet<_> = lhs_schemas.keys().cloned().collect();
+        let rhs_props: BTreeSet<_> = rhs_schemas.keys().cloned().collect();
+
+        for removed in lhs_props.difference(&rhs_props) {
+            (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::DependentSchemaRemove {
+                    property: removed.clone(),
+                },
+            });
+        }
+
+        for added in rhs_props.difference(&lhs_props) {
+            (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::DependentSchemaAdd {
+                    property: added.clone(),
+                },
+            });
+        }
+
+        for common in rhs_props.intersection(&lhs_props) {
+            let lhs_child = lhs_schemas.get_mut(common).unwrap();
+            let rhs_child = rhs_schemas.get_mut(common).unwrap();
+
+            let new_path = format!("{json_path}.<dependentSchemas:{common}>");
+            self.diff(&new_path, lhs_child, rhs_child)?;
+        }
+
+        Ok(())
+    }
+
     fn diff_format(&mut self, json_path: &str, lhs: &mut SchemaObject, rhs: &mut SchemaObject) {
         match (&lhs.format, &rhs.format) {
             (Some(lhs_fmt), Some(rhs_fmt)) if lhs_fmt != rhs_fmt => {
                 (self.cb)(Change {
                     path: json_path.to_owned(),
+                    nesting: self.nesting,
                     change: ChangeKind::FormatChange {
                         old_format: lhs_fmt.clone(),
                         new_format: rhs_fmt.clone(),
@@ -370,6 +1090,7 @@ impl<F: FnMut(Change)> DiffWalker<F> {
             (Some(removed_fmt), None) => {
                 (self.cb)(Change {
                     path: json_path.to_owned(),
+                    nesting: self.nesting,
                     change: ChangeKind::FormatRemove {
                         removed: removed_fmt.clone(),
                     },
@@ -378,6 +1099,7 @@ impl<F: FnMut(Change)> DiffWalker<F> {
             (None, Some(added_fmt)) => {
                 (self.cb)(Change {
                     path: json_path.to_owned(),
+                    nesting: self.nesting,
                     change: ChangeKind::FormatAdd {
                         added: added_fmt.clone(),
                     },
@@ -387,82 +1109,616 @@ impl<F: FnMut(Change)> DiffWalker<F> {
         }
     }
 
-    fn diff_enum(&mut self, json_path: &str, lhs: &mut SchemaObject, rhs: &mut SchemaObject) {
-        let lhs_enum = lhs.enum_values.as_deref().unwrap_or(&[]);
-        let rhs_enum = rhs.enum_values.as_deref().unwrap_or(&[]);
-        let lhs_has_no_enum = lhs.enum_values.is_none();
-        let rhs_has_no_enum = rhs.enum_values.is_none();
+    fn diff_content(
+        &mut self,
+        json_path: &str,
+        lhs: &mut SchemaObject,
+        rhs: &mut SchemaObject,
+    ) -> Result<(), Error> {
+        let keyword = |schema: &SchemaObject, keyword: &str| {
+            schema
+                .extensions
+                .get(keyword)
+                .and_then(Value::as_str)
+                .map(str::to_owned)
+        };
+
+        // Both encodings and media types are case-insensitive.
+        match (
+            keyword(lhs, "contentEncoding"),
+            keyword(rhs, "contentEncoding"),
+        ) {
+            (Some(old_encoding), Some(new_encoding))
+                if !old_encoding.eq_ignore_ascii_case(&new_encoding) =>
+            {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: ChangeKind::ContentEncodingChange {
+                        old_encoding,
+                        new_encoding,
+                    },
+                });
+            }
+            (Some(removed), None) => (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::ContentEncodingRemove { removed },
+            }),
+            (None, Some(added)) => (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::ContentEncodingAdd { added },
+            }),
+            _ => {}
+        }
+
+        match (
+            keyword(lhs, "contentMediaType"),
+            keyword(rhs, "contentMediaType"),
+        ) {
+            (Some(old_media_type), Some(new_media_type))
+                if !old_media_type.eq_ignore_ascii_case(&new_media_type) =>
+            {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: ChangeKind::ContentMediaTypeChange {
+                        old_media_type,
+                        new_media_type,
+                    },
+                });
+            }
+            (Some(removed), None) => (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::ContentMediaTypeRemove { removed },
+            }),
+            (None, Some(added)) => (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::ContentMediaTypeAdd { added },
+            }),
+            _ => {}
+        }
+
+        match (
+            Self::extension_schema(lhs, "contentSchema")?,
+            Self::extension_schema(rhs, "contentSchema")?,
+        ) {
+            (Some(mut lhs_content), Some(mut rhs_content)) => {
+                let new_path = format!("{json_path}.<content>");
+                self.diff(&new_path, &mut lhs_content, &mut rhs_content)?;
+            }
+            (Some(_), None) => (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::ContentSchemaRemove,
+            }),
+            (None, Some(_)) => (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::ContentSchemaAdd,
+            }),
+            (None, None) => (),
+        }
+
+        Ok(())
+    }
+
+    fn diff_read_write_only(
+        &mut self,
+        json_path: &str,
+        lhs: &mut SchemaObject,
+        rhs: &mut SchemaObject,
+    ) {
+        let flags = |schema: &SchemaObject| {
+            schema
+                .metadata
+                .as_ref()
+                .map_or((false, false), |m| (m.read_only, m.write_only))
+        };
+        let (lhs_read_only, lhs_write_only) = flags(lhs);
+        let (rhs_read_only, rhs_write_only) = flags(rhs);
+
+        match (lhs_read_only, rhs_read_only) {
+            (false, true) => (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::ReadOnlyAdd,
+            }),
+            (true, false) => (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::ReadOnlyRemove,
+            }),
+            _ => (),
+        }
+
+        match (lhs_write_only, rhs_write_only) {
+            (false, true) => (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::WriteOnlyAdd,
+            }),
+            (true, false) => (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::WriteOnlyRemove,
+            }),
+            _ => (),
+        }
+    }
+
+    fn diff_deprecated(&mut self, json_path: &str, lhs: &mut SchemaObject, rhs: &mut SchemaObject) {
+        if self.config.ignore_deprecations {
+            return;
+        }
+
+        let deprecated =
+            |schema: &SchemaObject| schema.metadata.as_ref().is_some_and(|m| m.deprecated);
+        match (deprecated(lhs), deprecated(rhs)) {
+            (false, true) => (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::DeprecationAdd,
+            }),
+            (true, false) => (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::DeprecationRemove,
+            }),
+            _ => (),
+        }
+    }
+
+    fn diff_default(&mut self, json_path: &str, lhs: &mut SchemaObject, rhs: &mut SchemaObject) {
+        let default = |schema: &SchemaObject| schema.metadata.as_ref()?.default.clone();
+        let lhs_default = default(lhs);
+        let rhs_default = default(rhs);
+
+        match (&lhs_default, &rhs_default) {
+            (Some(old_value), Some(new_value)) if !values_equal(old_value, new_value) => {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: ChangeKind::DefaultChange {
+                        old_value: old_value.clone(),
+                        new_value: new_value.clone(),
+                        breaks_consumers: self.config.defaults_break_consumers,
+                    },
+                });
+            }
+            (Some(removed), None) => (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::DefaultRemove {
+                    removed: removed.clone(),
+                    breaks_consumers: self.config.defaults_break_consumers,
+                },
+            }),
+            (None, Some(added)) => (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::DefaultAdd {
+                    added: added.clone(),
+                    breaks_consumers: self.config.defaults_break_consumers,
+                },
+            }),
+            _ => (),
+        }
+
+        // Don't report a default that was already invalid before.
+        if let Some(value) = rhs_default {
+            let was_invalid = lhs_default.is_some_and(|old_value| {
+                values_equal(&old_value, &value) && !default_is_valid(lhs, &old_value)
+            });
+            if !was_invalid && !default_is_valid(rhs, &value) {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: ChangeKind::DefaultInvalid { value },
+                });
+            }
+        }
+    }
+
+    fn diff_annotations(
+        &mut self,
+        json_path: &str,
+        lhs: &mut SchemaObject,
+        rhs: &mut SchemaObject,
+    ) {
+        if !self.config.diff_annotations {
+            return;
+        }
+
+        let lhs_metadata = lhs.metadata.as_deref().cloned().unwrap_or_default();
+        let rhs_metadata = rhs.metadata.as_deref().cloned().unwrap_or_default();
+        // schemars doesn't know about `$comment`.
+        let comment = |schema: &SchemaObject| {
+            schema
+                .extensions
+                .get("$comment")
+                .and_then(Value::as_str)
+                .map(str::to_owned)
+        };
+
+        let mut changes = vec![];
+        if lhs_metadata.title != rhs_metadata.title {
+            changes.push(ChangeKind::TitleChange {
+                old_value: lhs_metadata.title,
+                new_value: rhs_metadata.title,
+            });
+        }
+        if lhs_metadata.description != rhs_metadata.description {
+            changes.push(ChangeKind::DescriptionChange {
+                old_value: lhs_metadata.description,
+                new_value: rhs_metadata.description,
+            });
+        }
+        let examples_equal = lhs_metadata.examples.len() == rhs_metadata.examples.len()
+            && lhs_metadata
+                .examples
+                .iter()
+                .zip(&rhs_metadata.examples)
+                .all(|(l, r)| values_equal(l, r));
+        if !examples_equal {
+            changes.push(ChangeKind::ExamplesChange {
+                old_value: lhs_metadata.examples,
+                new_value: rhs_metadata.examples,
+            });
+        }
+        if comment(lhs) != comment(rhs) {
+            changes.push(ChangeKind::CommentChange {
+                old_value: comment(lhs),
+                new_value: comment(rhs),
+            });
+        }
+
+        for change in changes {
+            (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change,
+            });
+        }
+    }
+
+    fn diff_enum(&mut self, json_path: &str, lhs: &mut SchemaObject, rhs: &mut SchemaObject) {
+        let lhs_enum = lhs.enum_values.as_deref().unwrap_or(&[]);
+        let rhs_enum = rhs.enum_values.as_deref().unwrap_or(&[]);
+        let lhs_has_no_enum = lhs.enum_values.is_none();
+        let rhs_has_no_enum = rhs.enum_values.is_none();
+
+        // Find removed enum values (in lhs but not in rhs)
+        for lhs_value in lhs_enum {
+            if !rhs_enum.iter().any(|v| values_equal(v, lhs_value)) {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: ChangeKind::EnumRemove {
+                        removed: lhs_value.clone(),
+                        rhs_has_no_enum,
+                    },
+                });
+            }
+        }
+
+        // Find added enum values (in rhs but not in lhs)
+        for rhs_value in rhs_enum {
+            if !lhs_enum.iter().any(|v| values_equal(v, rhs_value)) {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: ChangeKind::EnumAdd {
+                        added: rhs_value.clone(),
+                        lhs_has_no_enum,
+                    },
+                });
+            }
+        }
+    }
+
+    fn diff_pattern(&mut self, json_path: &str, lhs: &mut SchemaObject, rhs: &mut SchemaObject) {
+        let lhs_pattern = &lhs.string().pattern;
+        let rhs_pattern = &rhs.string().pattern;
+
+        match (lhs_pattern, rhs_pattern) {
+            (Some(lhs_pat), Some(rhs_pat)) if lhs_pat != rhs_pat => {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: ChangeKind::PatternChange {
+                        old_pattern: lhs_pat.clone(),
+                        new_pattern: rhs_pat.clone(),
+                    },
+                });
+            }
+            (Some(removed_pat), None) => {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: ChangeKind::PatternRemove {
+                        removed: removed_pat.clone(),
+                    },
+                });
+            }
+            (None, Some(added_pat)) => {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: ChangeKind::PatternAdd {
+                        added: added_pat.clone(),
+                    },
+                });
+            }
+            _ => {} // No change or both None
+        }
+    }
+
+    fn diff_min_length(&mut self, json_path: &str, lhs: &mut SchemaObject, rhs: &mut SchemaObject) {
+        let lhs_min = lhs.string().min_length;
+        let rhs_min = rhs.string().min_length;
+
+        match (lhs_min, rhs_min) {
+            (Some(lhs_val), Some(rhs_val)) if lhs_val != rhs_val => {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: ChangeKind::MinLengthChange {
+                        old_value: lhs_val,
+                        new_value: rhs_val,
+                    },
+                });
+            }
+            (Some(removed_val), None) => {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: ChangeKind::MinLengthRemove {
+                        removed: removed_val,
+                    },
+                });
+            }
+            (None, Some(added_val)) => {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: ChangeKind::MinLengthAdd { added: added_val },
+                });
+            }
+            _ => {} // No change or both None
+        }
+    }
+
+    fn diff_max_length(&mut self, json_path: &str, lhs: &mut SchemaObject, rhs: &mut SchemaObject) {
+        let lhs_max = lhs.string().max_length;
+        let rhs_max = rhs.string().max_length;
+
+        match (lhs_max, rhs_max) {
+            (Some(lhs_val), Some(rhs_val)) if lhs_val != rhs_val => {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: ChangeKind::MaxLengthChange {
+                        old_value: lhs_val,
+                        new_value: rhs_val,
+                    },
+                });
+            }
+            (Some(removed_val), None) => {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: ChangeKind::MaxLengthRemove {
+                        removed: removed_val,
+                    },
+                });
+            }
+            (None, Some(added_val)) => {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: ChangeKind::MaxLengthAdd { added: added_val },
+                });
+            }
+            _ => {} // No change or both None
+        }
+    }
+
+    fn diff_min_items(&mut self, json_path: &str, lhs: &mut SchemaObject, rhs: &mut SchemaObject) {
+        let lhs_min = lhs.array().min_items;
+        let rhs_min = rhs.array().min_items;
+
+        match (lhs_min, rhs_min) {
+            (Some(lhs_val), Some(rhs_val)) if lhs_val != rhs_val => {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: ChangeKind::MinItemsChange {
+                        old_value: lhs_val,
+                        new_value: rhs_val,
+                    },
+                });
+            }
+            (Some(removed_val), None) => {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: ChangeKind::MinItemsRemove {
+                        removed: removed_val,
+                    },
+                });
+            }
+            (None, Some(added_val)) => {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: ChangeKind::MinItemsAdd { added: added_val },
+                });
+            }
+            _ => {} // No change or both None
+        }
+    }
+
+    fn diff_max_items(&mut self, json_path: &str, lhs: &mut SchemaObject, rhs: &mut SchemaObject) {
+        let lhs_max = lhs.array().max_items;
+        let rhs_max = rhs.array().max_items;
+
+        match (lhs_max, rhs_max) {
+            (Some(lhs_val), Some(rhs_val)) if lhs_val != rhs_val => {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: ChangeKind::MaxItemsChange {
+                        old_value: lhs_val,
+                        new_value: rhs_val,
+                    },
+                });
+            }
+            (Some(removed_val), None) => {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: ChangeKind::MaxItemsRemove {
+                        removed: removed_val,
+                    },
+                });
+            }
+            (None, Some(added_val)) => {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: ChangeKind::MaxItemsAdd { added: added_val },
+                });
+            }
+            _ => {} // No change or both None
+        }
+    }
+
+    fn diff_contains(
+        &mut self,
+        json_path: &str,
+        lhs: &mut SchemaObject,
+        rhs: &mut SchemaObject,
+    ) -> Result<(), Error> {
+        match (&mut lhs.array().contains, &mut rhs.array().contains) {
+            (Some(lhs_contains), Some(rhs_contains)) => {
+                let new_path = format!("{json_path}.<contains>");
+                self.diff(&new_path, lhs_contains, rhs_contains)?;
+            }
+            (Some(_), None) => (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::ContainsRemove,
+            }),
+            (None, Some(_)) => (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::ContainsAdd,
+            }),
+            (None, None) => (),
+        }
 
-        // Find removed enum values (in lhs but not in rhs)
-        for lhs_value in lhs_enum {
-            if !rhs_enum.contains(lhs_value) {
+        Ok(())
+    }
+
+    fn diff_min_contains(
+        &mut self,
+        json_path: &str,
+        lhs: &mut SchemaObject,
+        rhs: &mut SchemaObject,
+    ) {
+        let lhs_min = Self::contains_count(lhs, "minContains");
+        let rhs_min = Self::contains_count(rhs, "minContains");
+
+        match (lhs_min, rhs_min) {
+            (Some(lhs_val), Some(rhs_val)) if lhs_val != rhs_val => {
                 (self.cb)(Change {
                     path: json_path.to_owned(),
-                    change: ChangeKind::EnumRemove {
-                        removed: lhs_value.clone(),
-                        rhs_has_no_enum,
+                    nesting: self.nesting,
+                    change: ChangeKind::MinContainsChange {
+                        old_value: lhs_val,
+                        new_value: rhs_val,
                     },
                 });
             }
-        }
-
-        // Find added enum values (in rhs but not in lhs)
-        for rhs_value in rhs_enum {
-            if !lhs_enum.contains(rhs_value) {
+            (Some(removed_val), None) => {
                 (self.cb)(Change {
                     path: json_path.to_owned(),
-                    change: ChangeKind::EnumAdd {
-                        added: rhs_value.clone(),
-                        lhs_has_no_enum,
+                    nesting: self.nesting,
+                    change: ChangeKind::MinContainsRemove {
+                        removed: removed_val,
                     },
                 });
             }
+            (None, Some(added_val)) => {
+                (self.cb)(Change {
+                    path: json_path.to_owned(),
+                    nesting: self.nesting,
+                    change: ChangeKind::MinContainsAdd { added: added_val },
+                });
+            }
+            _ => {} // No change or both None
         }
     }
 
-    fn diff_pattern(&mut self, json_path: &str, lhs: &mut SchemaObject, rhs: &mut SchemaObject) {
-        let lhs_pattern = &lhs.string().pattern;
-        let rhs_pattern = &rhs.string().pattern;
+    fn diff_max_contains(
+        &mut self,
+        json_path: &str,
+        lhs: &mut SchemaObject,
+        rhs: &mut SchemaObject,
+    ) {
+        let lhs_max = Self::contains_count(lhs, "maxContains");
+        let rhs_max = Self::contains_count(rhs, "maxContains");
 
-        match (lhs_pattern, rhs_pattern) {
-            (Some(lhs_pat), Some(rhs_pat)) if lhs_pat != rhs_pat => {
+        match (lhs_max, rhs_max) {
+            (Some(lhs_val), Some(rhs_val)) if lhs_val != rhs_val => {
                 (self.cb)(Change {
                     path: json_path.to_owned(),
-                    change: ChangeKind::PatternChange {
-                        old_pattern: lhs_pat.clone(),
-                        new_pattern: rhs_pat.clone(),
+                    nesting: self.nesting,
+                    change: ChangeKind::MaxContainsChange {
+                        old_value: lhs_val,
+                        new_value: rhs_val,
                     },
                 });
             }
-            (Some(removed_pat), None) => {
+            (Some(removed_val), None) => {
                 (self.cb)(Change {
                     path: json_path.to_owned(),
-                    change: ChangeKind::PatternRemove {
-                        removed: removed_pat.clone(),
+                    nesting: self.nesting,
+                    change: ChangeKind::MaxContainsRemove {
+                        removed: removed_val,
                     },
                 });
             }
-            (None, Some(added_pat)) => {
+            (None, Some(added_val)) => {
                 (self.cb)(Change {
                     path: json_path.to_owned(),
-                    change: ChangeKind::PatternAdd {
-                        added: added_pat.clone(),
-                    },
+                    nesting: self.nesting,
+                    change: ChangeKind::MaxContainsAdd { added: added_val },
                 });
             }
             _ => {} // No change or both None
         }
     }
 
-    fn diff_min_length(&mut self, json_path: &str, lhs: &mut SchemaObject, rhs: &mut SchemaObject) {
-        let lhs_min = lhs.string().min_length;
-        let rhs_min = rhs.string().min_length;
+    fn diff_min_properties(
+        &mut self,
+        json_path: &str,
+        lhs: &mut SchemaObject,
+        rhs: &mut SchemaObject,
+    ) {
+        let lhs_min = lhs.object().min_properties;
+        let rhs_min = rhs.object().min_properties;
 
         match (lhs_min, rhs_min) {
             (Some(lhs_val), Some(rhs_val)) if lhs_val != rhs_val => {
                 (self.cb)(Change {
                     path: json_path.to_owned(),
-                    change: ChangeKind::MinLengthChange {
+                    nesting: self.nesting,
+                    change: ChangeKind::MinPropertiesChange {
                         old_value: lhs_val,
                         new_value: rhs_val,
                     },
@@ -471,7 +1727,8 @@ impl<F: FnMut(Change)> DiffWalker<F> {
             (Some(removed_val), None) => {
                 (self.cb)(Change {
                     path: json_path.to_owned(),
-                    change: ChangeKind::MinLengthRemove {
+                    nesting: self.nesting,
+                    change: ChangeKind::MinPropertiesRemove {
                         removed: removed_val,
                     },
                 });
@@ -479,22 +1736,29 @@ impl<F: FnMut(Change)> DiffWalker<F> {
             (None, Some(added_val)) => {
                 (self.cb)(Change {
                     path: json_path.to_owned(),
-                    change: ChangeKind::MinLengthAdd { added: added_val },
+                    nesting: self.nesting,
+                    change: ChangeKind::MinPropertiesAdd { added: added_val },
                 });
             }
             _ => {} // No change or both None
         }
     }
 
-    fn diff_max_length(&mut self, json_path: &str, lhs: &mut SchemaObject, rhs: &mut SchemaObject) {
-        let lhs_max = lhs.string().max_length;
-        let rhs_max = rhs.string().max_length;
+    fn diff_max_properties(
+        &mut self,
+        json_path: &str,
+        lhs: &mut SchemaObject,
+        rhs: &mut SchemaObject,
+    ) {
+        let lhs_max = lhs.object().max_properties;
+        let rhs_max = rhs.object().max_properties;
 
         match (lhs_max, rhs_max) {
             (Some(lhs_val), Some(rhs_val)) if lhs_val != rhs_val => {
                 (self.cb)(Change {
                     path: json_path.to_owned(),
-                    change: ChangeKind::MaxLengthChange {
+                    nesting: self.nesting,
+                    change: ChangeKind::MaxPropertiesChange {
                         old_value: lhs_val,
                         new_value: rhs_val,
                     },
@@ -503,7 +1767,8 @@ impl<F: FnMut(Change)> DiffWalker<F> {
             (Some(removed_val), None) => {
                 (self.cb)(Change {
                     path: json_path.to_owned(),
-                    change: ChangeKind::MaxLengthRemove {
+                    nesting: self.nesting,
+                    change: ChangeKind::MaxPropertiesRemove {
                         removed: removed_val,
                     },
                 });
@@ -511,13 +1776,153 @@ impl<F: FnMut(Change)> DiffWalker<F> {
             (None, Some(added_val)) => {
                 (self.cb)(Change {
                     path: json_path.to_owned(),
-                    change: ChangeKind::MaxLengthAdd { added: added_val },
+                    nesting: self.nesting,
+                    change: ChangeKind::MaxPropertiesAdd { added: added_val },
                 });
             }
             _ => {} // No change or both None
         }
     }
 
+    fn diff_unique_items(
+        &mut self,
+        json_path: &str,
+        lhs: &mut SchemaObject,
+        rhs: &mut SchemaObject,
+    ) {
+        // `"uniqueItems": false` is the same as omitting it.
+        let lhs_unique = lhs.array().unique_items.unwrap_or(false);
+        let rhs_unique = rhs.array().unique_items.unwrap_or(false);
+
+        match (lhs_unique, rhs_unique) {
+            (false, true) => (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::UniqueItemsAdd,
+            }),
+            (true, false) => (self.cb)(Change {
+                path: json_path.to_owned(),
+                nesting: self.nesting,
+                change: ChangeKind::UniqueItemsRemove,
+            }),
+            _ => {} // No change
+        }
+    }
+
+    /// The schema that validates properties that match neither `properties` nor
+    /// `patternProperties`, ignoring those evaluated by subschemas.
+    fn unmatched_property_schema(schema: &mut SchemaObject) -> Result<Schema, Error> {
+        match schema.object().additional_properties.as_deref() {
+            Some(additional_properties) => Ok(additional_properties.clone()),
+            None => Ok(Self::extension_schema(schema, "unevaluatedProperties")?
+                .unwrap_or(Schema::Bool(true))),
+        }
+    }
+
+    /// The schema that validates a property `name` that `schema` doesn't declare in `properties`:
+    /// the matching patternProperties, additionalProperties or unevaluatedProperties. `None` if
+    /// that is unknown, because a subschema evaluates the property instead or a patternProperties
+    /// regex can't be compiled.
+    fn undeclared_property_schema(
+        schema: &mut SchemaObject,
+        name: &str,
+    ) -> Result<Option<Schema>, Error> {
+        // A property matching any patternProperties is validated by those instead of
+        // additionalProperties. If it's unknown whether a pattern matches, so is the schema.
+        let mut matching = vec![];
+        for (pattern, schema) in &schema.object().pattern_properties {
+            match pattern_matches(pattern, name) {
+                Some(true) => matching.push(schema.clone()),
+                Some(false) => {}
+                None => return Ok(None),
+            }
+        }
+        if !matching.is_empty() {
+            let mut constraining: Vec<_> = matching
+                .into_iter()
+                .filter(|schema| !schema.clone().into_object().is_true())
+                .collect();
+            return Ok(Some(match constraining.len() {
+                0 => Schema::Bool(true),
+                1 => constraining.remove(0),
+                _ => Schema::Object(SchemaObject {
+                    subschemas: Some(Box::new(SubschemaValidation {
+                        all_of: Some(constraining),
+                        ..Default::default()
+                    })),
+                    ..Default::default()
+                }),
+            }));
+        }
+
+        if let Some(additional_properties) = schema.object().additional_properties.as_deref() {
+            return Ok(Some(additional_properties.clone()));
+        }
+
+        // Without additionalProperties, a property that no subschema evaluates is validated by
+        // unevaluatedProperties.
+        if evaluated_properties(schema).contains(name) {
+            return Ok(None);
+        }
+        Ok(Some(
+            Self::extension_schema(schema, "unevaluatedProperties")?.unwrap_or(Schema::Bool(true)),
+        ))
+    }
+
+    /// Read a subschema keyword that schemars doesn't know about, such as the 2019-09
+    /// `unevaluatedProperties`/`unevaluatedItems` or `contentSchema`.
+    fn extension_schema(
+        schema_object: &SchemaObject,
+        keyword: &str,
+    ) -> Result<Option<Schema>, Error> {
+        Ok(schema_object
+            .extensions
+            .get(keyword)
+            .cloned()
+            .map(serde_json::from_value)
+            .transpose()?)
+    }
+
+    /// Read the 2019-09 `minContains`/`maxContains` keywords, which schemars doesn't know about.
+    fn contains_count(schema_object: &SchemaObject, keyword: &str) -> Option<u32> {
+        schema_object
+            .extensions
+            .get(keyword)
+            .and_then(Value::as_u64)
+            .and_then(|count| u32::try_from(count).ok())
+    }
+
+    /// Collect draft-07 `dependencies` and 2019-09 `dependentRequired`/`dependentSchemas`.
+    fn dependencies(schema_object: &SchemaObject) -> Result<Dependencies, Error> {
+        let mut dependencies = Dependencies::default();
+
+        for keyword in ["dependencies", "dependentRequired", "dependentSchemas"] {
+            let Some(Value::Object(by_property)) = schema_object.extensions.get(keyword) else {
+                continue;
+            };
+
+            for (property, dependency) in by_property {
+                match dependency {
+                    Value::Array(_) if keyword != "dependentSchemas" => {
+                        let dependents: Vec<String> = serde_json::from_value(dependency.clone())?;
+                        dependencies
+                            .required
+                            .entry(property.clone())
+                            .or_default()
+                            .extend(dependents);
+                    }
+                    _ if keyword != "dependentRequired" => {
+                        let schema: Schema = serde_json::from_value(dependency.clone())?;
+                        dependencies.schemas.insert(property.clone(), schema);
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        Ok(dependencies)
+    }
+
     fn resolve_references(
         &self,
         lhs: &mut SchemaObject,
@@ -538,6 +1943,65 @@ impl<F: FnMut(Change)> DiffWalker<F> {
         Ok(())
     }
 
+    /// Merge `allOf` branches into the schema that contains them, so that constraints are compared
+    /// regardless of which branch they are declared in.
+    fn merge_all_of(&self, lhs: &mut SchemaObject, rhs: &mut SchemaObject) {
+        // An anyOf or oneOf with a single variant is equivalent to an allOf branch. Unless there
+        // are variants on the other side to pair it with, merge it like one.
+        for kind in [Composition::AnyOf, Composition::OneOf] {
+            let lhs_has_variants = kind.variants(lhs).is_some();
+            let rhs_has_variants = kind.variants(rhs).is_some();
+            if !rhs_has_variants {
+                kind.single_variant_into_all_of(lhs);
+            }
+            if !lhs_has_variants {
+                kind.single_variant_into_all_of(rhs);
+            }
+        }
+
+        *lhs = merge::merge_all_of(lhs, &|reference| {
+            self.lhs_resolver
+                .resolve(&self.lhs_root, reference)
+                .cloned()
+        });
+        *rhs = merge::merge_all_of(rhs, &|reference| {
+            self.rhs_resolver
+                .resolve(&self.rhs_root, reference)
+                .cloned()
+        });
+    }
+
+    /// Rewrite the draft 2020-12 `prefixItems` and `items` keywords into the `items` and
+    /// `additionalItems` keywords of earlier drafts, which is what schemars parses them as.
+    fn normalize_array_keywords(
+        schema_object: &mut SchemaObject,
+        draft: Draft,
+    ) -> Result<(), Error> {
+        if draft != Draft::Draft2020_12 {
+            return Ok(());
+        }
+
+        let prefix_items = schema_object.extensions.remove("prefixItems");
+        if prefix_items.is_none() && schema_object.array.is_none() {
+            return Ok(());
+        }
+
+        let array = schema_object.array();
+        match prefix_items {
+            Some(prefix_items) => {
+                array.additional_items = match array.items.take() {
+                    Some(SingleOrVec::Single(items)) => Some(items),
+                    _ => None,
+                };
+                array.items = Some(SingleOrVec::Vec(serde_json::from_value(prefix_items)?));
+            }
+            // additionalItems does not exist in 2020-12.
+            None => array.additional_items = None,
+        }
+
+        Ok(())
+    }
+
     fn restrictions_for_single_type(schema_object: &SchemaObject, ty: InstanceType) -> Schema {
         let mut ret = SchemaObject {
             instance_type: Some(SingleOrVec::Single(Box::new(ty))),
@@ -555,12 +2019,25 @@ impl<F: FnMut(Change)> DiffWalker<F> {
         Schema::Object(ret)
     }
 
+    /// The restrictions of a schema with a single type, as the only variant of an `anyOf` like
+    /// `Self::split_types` would produce.
+    fn single_type_variant(schema_object: &mut SchemaObject) -> Option<(Composition, Vec<Schema>)> {
+        match schema_object.effective_type() {
+            InternalJsonSchemaType::Simple(ty) => Some((
+                Composition::AnyOf,
+                vec![Self::restrictions_for_single_type(schema_object, ty.into())],
+            )),
+            _ => None,
+        }
+    }
+
     /// Split a schema into multiple schemas, one for each type in the multiple type.
     /// Returns the new schema and whether the schema was changed.
     fn split_types(schema_object: &mut SchemaObject) -> bool {
         let is_split = match schema_object.effective_type() {
             InternalJsonSchemaType::Multiple(types)
-                if schema_object.subschemas().any_of.is_none() =>
+                if schema_object.subschemas().any_of.is_none()
+                    && schema_object.subschemas().one_of.is_none() =>
             {
                 *schema_object = SchemaObject {
                     subschemas: Some(Box::new(SubschemaValidation {
@@ -583,45 +2060,87 @@ impl<F: FnMut(Change)> DiffWalker<F> {
         is_split
     }
 
-    fn normalize_const(schema_object: &mut SchemaObject) {
-        fn do_normalize(value: Value) -> SchemaObject {
-            match value {
-                Value::Object(obj) => {
-                    let properties = obj
-                        .into_iter()
-                        .map(|(k, v)| (k, Schema::Object(do_normalize(v))))
-                        .collect::<BTreeMap<_, _>>();
-                    SchemaObject {
-                        object: Some(Box::new(ObjectValidation {
-                            properties,
-                            ..Default::default()
-                        })),
+    /// Expand a const object into a schema with a const for each property, so that changes are
+    /// reported at the property that changed. A const array is expanded into a tuple likewise if
+    /// `expand_array`, i.e. if the other side is a const array as well.
+    fn normalize_const(schema_object: &mut SchemaObject, expand_array: bool) {
+        let constant = |value| {
+            Schema::Object(SchemaObject {
+                const_value: Some(value),
+                ..Default::default()
+            })
+        };
+        match schema_object.const_value.take() {
+            Some(Value::Object(obj)) => {
+                *schema_object = SchemaObject {
+                    object: Some(Box::new(ObjectValidation {
+                        properties: obj.into_iter().map(|(k, v)| (k, constant(v))).collect(),
                         ..Default::default()
-                    }
+                    })),
+                    ..Default::default()
                 }
-                _ => SchemaObject {
-                    const_value: Some(value),
+            }
+            // Fix the length, so that adding or removing elements is a breaking change.
+            Some(Value::Array(arr)) if expand_array => {
+                *schema_object = SchemaObject {
+                    instance_type: Some(InstanceType::Array.into()),
+                    array: Some(Box::new(ArrayValidation {
+                        min_items: u32::try_from(arr.len()).ok(),
+                        additional_items: Some(Box::new(Schema::Bool(false))),
+                        items: Some(SingleOrVec::Vec(arr.into_iter().map(constant).collect())),
+                        ..Default::default()
+                    })),
                     ..Default::default()
-                },
+                }
             }
+            value => schema_object.const_value = value,
         }
+    }
+
+    /// Move a const into `enum`, keeping only the values allowed by both.
+    fn const_into_enum(schema_object: &mut SchemaObject) {
         if let Some(value) = schema_object.const_value.take() {
-            *schema_object = do_normalize(value)
+            let values = match schema_object.enum_values.take() {
+                Some(values) => values
+                    .into_iter()
+                    .filter(|v| values_equal(v, &value))
+                    .collect(),
+                None => vec![value],
+            };
+            schema_object.enum_values = Some(values);
         }
     }
 
     fn do_diff(
         &mut self,
         json_path: &str,
-        // Whether we are comparing elements in any_of subschemas
+        // Whether we are comparing elements in anyOf/oneOf subschemas
         comparing_any_of: bool,
         lhs: &mut SchemaObject,
         rhs: &mut SchemaObject,
     ) -> Result<(), Error> {
         self.resolve_references(lhs, rhs)?;
+        self.merge_all_of(lhs, rhs);
+        Self::normalize_array_keywords(lhs, self.lhs_draft)?;
+        Self::normalize_array_keywords(rhs, self.rhs_draft)?;
+        // A const is an enum with a single value. Compare it as such when either side has an enum,
+        // so that only the values that are actually added or removed get reported, and the type
+        // that the const implies doesn't get compared with the enum.
+        if lhs.enum_values.is_some() || rhs.enum_values.is_some() {
+            Self::const_into_enum(lhs);
+            Self::const_into_enum(rhs);
+        }
+        // Annotations are lost when splitting types, so compare them beforehand.
+        self.diff_read_write_only(json_path, lhs, rhs);
+        self.diff_deprecated(json_path, lhs, rhs);
+        self.diff_default(json_path, lhs, rhs);
+        self.diff_annotations(json_path, lhs, rhs);
         let is_lhs_split = Self::split_types(lhs);
         let is_rhs_split = Self::split_types(rhs);
-        self.diff_any_of(json_path, is_rhs_split, lhs, rhs)?;
+        self.diff_any_of(json_path, is_lhs_split, is_rhs_split, lhs, rhs)?;
+        self.diff_all_of(json_path, lhs, rhs)?;
+        self.diff_not(json_path, lhs, rhs)?;
+        self.diff_conditional(json_path, lhs, rhs)?;
         if !comparing_any_of {
             self.diff_instance_types(json_path, lhs, rhs);
         }
@@ -631,14 +2150,29 @@ impl<F: FnMut(Change)> DiffWalker<F> {
         self.diff_pattern(json_path, lhs, rhs);
         self.diff_min_length(json_path, lhs, rhs);
         self.diff_max_length(json_path, lhs, rhs);
+        self.diff_content(json_path, lhs, rhs)?;
         // If we split the types, we don't want to compare type-specific properties
         // because they are already compared in the `Self::diff_any_of`
         if !is_lhs_split && !is_rhs_split {
             self.diff_properties(json_path, lhs, rhs)?;
+            self.diff_pattern_properties(json_path, lhs, rhs)?;
             self.diff_range(json_path, lhs, rhs)?;
+            self.diff_multiple_of(json_path, lhs, rhs);
             self.diff_additional_properties(json_path, lhs, rhs)?;
+            self.diff_property_names(json_path, lhs, rhs)?;
+            self.diff_unevaluated(json_path, "unevaluatedProperties", lhs, rhs)?;
             self.diff_array_items(json_path, lhs, rhs)?;
+            self.diff_unevaluated(json_path, "unevaluatedItems", lhs, rhs)?;
+            self.diff_min_items(json_path, lhs, rhs);
+            self.diff_max_items(json_path, lhs, rhs);
+            self.diff_unique_items(json_path, lhs, rhs);
+            self.diff_contains(json_path, lhs, rhs)?;
+            self.diff_min_contains(json_path, lhs, rhs);
+            self.diff_max_contains(json_path, lhs, rhs);
             self.diff_required(json_path, lhs, rhs)?;
+            self.diff_dependencies(json_path, lhs, rhs)?;
+            self.diff_min_properties(json_path, lhs, rhs);
+            self.diff_max_properties(json_path, lhs, rhs);
         }
         Ok(())
     }
@@ -692,7 +2226,11 @@ impl JsonSchemaExt for SchemaObject {
             serde_value_to_own(constant).into()
         } else if !self.object().properties.is_empty() {
             JsonSchemaType::Object.into()
-        } else if let Some(ref any_of) = self.subschemas().any_of {
+        } else if let Some(any_of) = self
+            .subschemas
+            .as_ref()
+            .and_then(|s| s.any_of.as_ref().or(s.one_of.as_ref()))
+        {
             InternalJsonSchemaType::Multiple(
                 any_of
                     .iter()
@@ -729,6 +2267,179 @@ impl JsonSchemaExt for SchemaObject {
     }
 }
 
+/// The properties and subschemas that the presence of a property triggers.
+#[derive(Default)]
+struct Dependencies {
+    required: BTreeMap<String, BTreeSet<String>>,
+    schemas: BTreeMap<String, Schema>,
+}
+
+/// The keywords that combine subschemas as a list of alternatives.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+enum Composition {
+    AnyOf,
+    OneOf,
+}
+
+impl Composition {
+    fn of_schema(schema: &mut SchemaObject) -> Vec<(Composition, Vec<Schema>)> {
+        let subschemas = schema.subschemas();
+        let mut ret = vec![];
+        if let Some(ref any_of) = subschemas.any_of {
+            ret.push((Composition::AnyOf, any_of.clone()));
+        }
+        if let Some(ref one_of) = subschemas.one_of {
+            ret.push((Composition::OneOf, one_of.clone()));
+        }
+        ret
+    }
+
+    fn variants(self, schema: &mut SchemaObject) -> &mut Option<Vec<Schema>> {
+        let subschemas = schema.subschemas();
+        match self {
+            Composition::AnyOf => &mut subschemas.any_of,
+            Composition::OneOf => &mut subschemas.one_of,
+        }
+    }
+
+    /// Move the variant of a composition with only one variant into `allOf`.
+    fn single_variant_into_all_of(self, schema: &mut SchemaObject) {
+        if let Some(variants) = self
+            .variants(schema)
+            .take_if(|variants| variants.len() == 1)
+        {
+            schema
+                .subschemas()
+                .all_of
+                .get_or_insert_with(Vec::new)
+                .extend(variants);
+        }
+    }
+
+    fn keyword(self) -> &'static str {
+        match self {
+            Composition::AnyOf => "anyOf",
+            Composition::OneOf => "oneOf",
+        }
+    }
+
+    fn added(self) -> ChangeKind {
+        match self {
+            Composition::AnyOf => ChangeKind::AnyOfAdd,
+            Composition::OneOf => ChangeKind::OneOfAdd,
+        }
+    }
+
+    fn removed(self) -> ChangeKind {
+        match self {
+            Composition::AnyOf => ChangeKind::AnyOfRemove,
+            Composition::OneOf => ChangeKind::OneOfRemove,
+        }
+    }
+}
+
+/// Whether the variants of a composition only restrict the type of `schema`, which is already
+/// covered by the diff of its effective type.
+fn only_restricts_type(
+    schema: &SchemaObject,
+    (kind, variants): &(Composition, Vec<Schema>),
+) -> bool {
+    let determines_type = schema.instance_type.is_none()
+        && schema.const_value.is_none()
+        && schema
+            .object
+            .as_ref()
+            .is_none_or(|object| object.properties.is_empty())
+        && (*kind == Composition::AnyOf
+            || schema
+                .subschemas
+                .as_ref()
+                .is_none_or(|s| s.any_of.is_none()));
+
+    determines_type
+        && variants.iter().all(|variant| match variant {
+            Schema::Object(variant) => SchemaObject {
+                instance_type: None,
+                ..variant.clone()
+            }
+            .is_true(),
+            Schema::Bool(_) => false,
+        })
+}
+
+/// Find all pairs of variants (by index, lower index first) that a single value could match at
+/// the same time.
+fn overlapping_variants(
+    variants: &[Schema],
+    resolve: &dyn Fn(&str) -> Option<Schema>,
+) -> BTreeSet<(usize, usize)> {
+    let variants: Vec<_> = variants
+        .iter()
+        .map(|variant| {
+            let variant = variant.clone().into_object();
+            let variant = match variant.reference {
+                Some(ref reference) => resolve(reference).map_or(variant, Schema::into_object),
+                None => variant,
+            };
+            merge::merge_all_of(&variant, resolve)
+        })
+        .collect();
+
+    let mut ret = BTreeSet::new();
+    for (i, lhs) in variants.iter().enumerate() {
+        for (j, rhs) in variants.iter().enumerate().skip(i + 1) {
+            if may_overlap(lhs, rhs) {
+                ret.insert((i, j));
+            }
+        }
+    }
+    ret
+}
+
+/// Conservatively determine whether two schemas can be matched by the same value.
+///
+/// Schemas are only considered disjoint if they allow different types, allow different constant
+/// values, or both constrain a property to different constant values (a discriminator).
+fn may_overlap(lhs: &SchemaObject, rhs: &SchemaObject) -> bool {
+    let lhs_ty = lhs.clone().effective_type().into_set();
+    let rhs_ty = rhs.clone().effective_type().into_set();
+    if lhs_ty.is_disjoint(&rhs_ty) {
+        return false;
+    }
+
+    if !values_may_overlap(lhs, rhs) {
+        return false;
+    }
+
+    let (Some(lhs_obj), Some(rhs_obj)) = (&lhs.object, &rhs.object) else {
+        return true;
+    };
+    !lhs_obj.properties.iter().any(|(name, lhs_prop)| {
+        let is_required = lhs_obj.required.contains(name) || rhs_obj.required.contains(name);
+        rhs_obj.properties.get(name).is_some_and(|rhs_prop| {
+            is_required
+                && !values_may_overlap(
+                    &lhs_prop.clone().into_object(),
+                    &rhs_prop.clone().into_object(),
+                )
+        })
+    })
+}
+
+fn values_may_overlap(lhs: &SchemaObject, rhs: &SchemaObject) -> bool {
+    let allowed_values = |schema: &SchemaObject| match (&schema.const_value, &schema.enum_values) {
+        (Some(value), _) => Some(vec![value.clone()]),
+        (None, Some(values)) => Some(values.clone()),
+        (None, None) => None,
+    };
+    match (allowed_values(lhs), allowed_values(rhs)) {
+        (Some(lhs_values), Some(rhs_values)) => lhs_values
+            .iter()
+            .any(|l| rhs_values.iter().any(|r| values_equal(l, r))),
+        _ => true,
+    }
+}
+
 #[derive(Clone, Ord, Eq, PartialEq, PartialOrd, Debug)]
 enum InternalJsonSchemaType {
     Simple(JsonSchemaType),
@@ -773,6 +2484,89 @@ impl InternalJsonSchemaType {
     }
 }
 
+/// Collect the names of properties declared by subschemas of `schema`, which count as evaluated
+/// for the purpose of `unevaluatedProperties`.
+fn evaluated_properties(schema: &SchemaObject) -> BTreeSet<String> {
+    let mut ret = BTreeSet::new();
+    let Some(ref subschemas) = schema.subschemas else {
+        return ret;
+    };
+
+    let lists = [&subschemas.all_of, &subschemas.any_of, &subschemas.one_of];
+    let singles = [
+        &subschemas.if_schema,
+        &subschemas.then_schema,
+        &subschemas.else_schema,
+    ];
+    let nested = lists
+        .into_iter()
+        .flatten()
+        .flatten()
+        .chain(singles.into_iter().flatten().map(|s| &**s));
+
+    for subschema in nested {
+        if let Schema::Object(subschema) = subschema {
+            if let Some(ref object) = subschema.object {
+                ret.extend(object.properties.keys().cloned());
+            }
+            ret.extend(evaluated_properties(subschema));
+        }
+    }
+
+    ret
+}
+
+/// Whether `value` matches the type, enum and const of `schema`. Other keywords aren't checked.
+fn default_is_valid(schema: &SchemaObject, value: &Value) -> bool {
+    let value_type = serde_value_to_own(value);
+    let is_integer = value
+        .as_f64()
+        .is_some_and(|n| n.fract() == 0.0 && n.is_finite());
+    let types = schema.clone().effective_type().into_set();
+    let type_matches =
+        types.contains(&value_type) || (is_integer && types.contains(&JsonSchemaType::Integer));
+
+    type_matches
+        && schema
+            .enum_values
+            .as_ref()
+            .is_none_or(|values| values.iter().any(|v| values_equal(v, value)))
+        && schema
+            .const_value
+            .as_ref()
+            .is_none_or(|v| values_equal(v, value))
+}
+
+/// Whether an undeclared property may have any value, see
+/// [`DiffWalker::undeclared_property_schema`].
+fn allows_any(schema: Option<Schema>) -> bool {
+    schema.is_none_or(|schema| schema.into_object().is_true())
+}
+
+/// Whether `schema` allows either any value or none at all, in which case there is nothing to
+/// diff a property against.
+fn is_trivial(schema: &Schema) -> bool {
+    matches!(schema, Schema::Bool(_)) || schema.clone().into_object().is_true()
+}
+
+/// Whether a tuple with `additional_items` validates every item against `items`, like a
+/// 2020-12 `prefixItems` that repeats `items`, so that it is no different from an array.
+fn tuple_is_array(tuple: &[Schema], additional_items: &Schema, items: &Schema) -> bool {
+    let items = items.clone().into_object();
+    tuple
+        .iter()
+        .chain([additional_items])
+        .all(|schema| schema.clone().into_object() == items)
+}
+
+/// Whether the patternProperties regex `pattern` matches the property name `name`.
+///
+/// `None` if the pattern can't be compiled, e.g. because it uses ECMA-262 features such as
+/// lookaround or backreferences that the regex crate doesn't support.
+fn pattern_matches(pattern: &str, name: &str) -> Option<bool> {
+    regex::Regex::new(pattern).ok().map(|re| re.is_match(name))
+}
+
 fn serde_value_to_own(val: &Value) -> JsonSchemaType {
     match val {
         Value::Number(_) => JsonSchemaType::Number,
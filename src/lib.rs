@@ -6,6 +6,7 @@ use serde_json::Value;
 use thiserror::Error;
 
 mod diff_walker;
+mod merge;
 mod resolver;
 mod types;
 
@@ -15,6 +16,13 @@ pub use types::*;
 ///
 /// `lhs` (left-hand side) is the old schema, `rhs` (right-hand side) is the new schema.
 pub fn diff(lhs: Value, rhs: Value) -> Result<Vec<Change>, Error> {
+    diff_with_config(lhs, rhs, &Config::default())
+}
+
+/// Take two JSON schemas, and compare them using the given options.
+///
+/// `lhs` (left-hand side) is the old schema, `rhs` (right-hand side) is the new schema.
+pub fn diff_with_config(lhs: Value, rhs: Value, config: &Config) -> Result<Vec<Change>, Error> {
     let lhs_root: RootSchema = serde_json::from_value(lhs)?;
     let rhs_root: RootSchema = serde_json::from_value(rhs)?;
 
@@ -25,6 +33,7 @@ pub fn diff(lhs: Value, rhs: Value) -> Result<Vec<Change>, Error> {
         },
         lhs_root,
         rhs_root,
+        config.clone(),
     );
     walker.diff(
         "",
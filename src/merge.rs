@@ -0,0 +1,468 @@
+use std::collections::BTreeSet;
+
+use schemars::schema::{
+    ArrayValidation, InstanceType, Metadata, NumberValidation, ObjectValidation, Schema,
+    SchemaObject, SingleOrVec, StringValidation, SubschemaValidation,
+};
+
+use crate::types::{is_multiple_of, values_equal};
+
+/// Merges the `allOf` branches of a schema into the schema itself.
+///
+/// `resolve` is used to look up branches that consist of a `$ref`. Branches that conflict with the
+/// schema or with another branch (for example two different `pattern`s) are kept in `allOf` so
+/// that the caller can diff them individually. Which branches are merged doesn't depend on their
+/// order, and merged branches are replaced with `true` so that the others keep their index.
+pub fn merge_all_of(
+    schema: &SchemaObject,
+    resolve: &dyn Fn(&str) -> Option<Schema>,
+) -> SchemaObject {
+    let Some(branches) = schema.subschemas.as_ref().and_then(|s| s.all_of.as_ref()) else {
+        return schema.clone();
+    };
+
+    let mut parent = schema.clone();
+    parent.subschemas.as_mut().unwrap().all_of = None;
+
+    let resolved: Vec<_> = branches
+        .iter()
+        .map(|branch| match branch {
+            Schema::Bool(true) => Some(SchemaObject::default()),
+            Schema::Bool(false) => None,
+            Schema::Object(obj) => match obj.reference {
+                Some(ref reference) => resolve(reference)
+                    .map(|resolved| merge_all_of(&resolved.into_object(), resolve)),
+                None => Some(merge_all_of(obj, resolve)),
+            },
+        })
+        .collect();
+    let merge_with_parent = |branches: &[&SchemaObject]| {
+        let mut merged = parent.clone();
+        for &branch in branches {
+            merge_into(&mut merged, branch.clone())?;
+        }
+        Some(merged)
+    };
+
+    let mut is_mergeable: Vec<_> = resolved
+        .iter()
+        .map(|branch| {
+            branch
+                .as_ref()
+                .is_some_and(|b| merge_with_parent(&[b]).is_some())
+        })
+        .collect();
+    let conflicting: Vec<_> = (0..resolved.len())
+        .flat_map(|i| (i + 1..resolved.len()).map(move |j| (i, j)))
+        .filter(|&(i, j)| match (&resolved[i], &resolved[j]) {
+            (Some(lhs), Some(rhs)) if is_mergeable[i] && is_mergeable[j] => {
+                merge_with_parent(&[lhs, rhs]).is_none()
+            }
+            _ => false,
+        })
+        .collect();
+    for (i, j) in conflicting {
+        is_mergeable[i] = false;
+        is_mergeable[j] = false;
+    }
+
+    let mergeable: Vec<_> = resolved
+        .iter()
+        .zip(&is_mergeable)
+        .filter_map(|(branch, &is_mergeable)| branch.as_ref().filter(|_| is_mergeable))
+        .collect();
+    // Conflicts between more than two branches are rare enough to not merge anything at all.
+    let mut merged = match merge_with_parent(&mergeable) {
+        Some(merged) => merged,
+        None => {
+            is_mergeable.fill(false);
+            parent
+        }
+    };
+
+    let mut unmerged: Vec<_> = branches
+        .iter()
+        .zip(is_mergeable)
+        .map(|(branch, is_mergeable)| match is_mergeable {
+            true => Schema::Bool(true),
+            false => branch.clone(),
+        })
+        .collect();
+    while unmerged.last() == Some(&Schema::Bool(true)) {
+        unmerged.pop();
+    }
+
+    let subschemas = merged.subschemas.get_or_insert_with(Default::default);
+    if !unmerged.is_empty() {
+        subschemas.all_of = Some(unmerged);
+    }
+    if **subschemas == SubschemaValidation::default() {
+        merged.subschemas = None;
+    }
+
+    merged
+}
+
+/// Combine both schemas into `target` so that it accepts exactly the values that are accepted by
+/// both. Returns `None` if that cannot be expressed, in which case `target` is left in an
+/// unspecified state.
+fn merge_into(target: &mut SchemaObject, source: SchemaObject) -> Option<()> {
+    let SchemaObject {
+        metadata,
+        instance_type,
+        format,
+        enum_values,
+        const_value,
+        subschemas,
+        number,
+        string,
+        array,
+        object,
+        reference,
+        extensions,
+    } = source;
+
+    if reference.is_some() || target.reference.is_some() {
+        return None;
+    }
+
+    merge_metadata(&mut target.metadata, metadata);
+    target.instance_type = match (target.instance_type.take(), instance_type) {
+        (Some(l), Some(r)) => Some(intersect_types(l, r)?),
+        (l, r) => l.or(r),
+    };
+    merge_eq(&mut target.format, format)?;
+    target.enum_values = match (target.enum_values.take(), enum_values) {
+        (Some(l), Some(r)) => {
+            let intersection: Vec<_> = l
+                .into_iter()
+                .filter(|v| r.iter().any(|r| values_equal(v, r)))
+                .collect();
+            if intersection.is_empty() {
+                return None;
+            }
+            Some(intersection)
+        }
+        (l, r) => l.or(r),
+    };
+    target.const_value = match (target.const_value.take(), const_value) {
+        (Some(l), Some(r)) if !values_equal(&l, &r) => return None,
+        (l, r) => l.or(r),
+    };
+    merge_boxed(&mut target.subschemas, subschemas, merge_subschemas)?;
+    merge_boxed(&mut target.number, number, merge_number)?;
+    merge_boxed(&mut target.string, string, merge_string)?;
+    merge_boxed(&mut target.array, array, merge_array)?;
+    merge_boxed(&mut target.object, object, merge_object)?;
+
+    for (key, value) in extensions {
+        match target.extensions.get(&key) {
+            Some(existing) if *existing != value => return None,
+            _ => {
+                target.extensions.insert(key, value);
+            }
+        }
+    }
+
+    Some(())
+}
+
+fn merge_metadata(target: &mut Option<Box<Metadata>>, source: Option<Box<Metadata>>) {
+    let Some(source) = source else { return };
+    let target = target.get_or_insert_with(Default::default);
+    target.id = target.id.take().or(source.id);
+    target.title = target.title.take().or(source.title);
+    target.description = target.description.take().or(source.description);
+    target.default = target.default.take().or(source.default);
+    target.deprecated |= source.deprecated;
+    target.read_only |= source.read_only;
+    target.write_only |= source.write_only;
+    for example in source.examples {
+        if !target.examples.contains(&example) {
+            target.examples.push(example);
+        }
+    }
+}
+
+fn merge_subschemas(target: &mut SubschemaValidation, source: SubschemaValidation) -> Option<()> {
+    // `then` and `else` only apply together with the `if` next to them, so they can't be merged
+    // with the conditional of another branch.
+    let mut target_conditional = conditional(target);
+    merge_eq(&mut target_conditional, conditional(&source))?;
+    (target.if_schema, target.then_schema, target.else_schema) =
+        target_conditional.unwrap_or_default();
+
+    merge_eq(&mut target.all_of, source.all_of)?;
+    merge_eq(&mut target.any_of, source.any_of)?;
+    merge_eq(&mut target.one_of, source.one_of)?;
+    merge_eq(&mut target.not, source.not)?;
+    Some(())
+}
+
+type Conditional = (
+    Option<Box<Schema>>,
+    Option<Box<Schema>>,
+    Option<Box<Schema>>,
+);
+
+/// The `if`, `then` and `else` of a schema, or `None` if it has no `if`, as `then` and `else` have
+/// no effect without it.
+fn conditional(subschemas: &SubschemaValidation) -> Option<Conditional> {
+    subschemas.if_schema.as_ref()?;
+    Some((
+        subschemas.if_schema.clone(),
+        subschemas.then_schema.clone(),
+        subschemas.else_schema.clone(),
+    ))
+}
+
+fn merge_number(target: &mut NumberValidation, source: NumberValidation) -> Option<()> {
+    target.multiple_of = match (target.multiple_of, source.multiple_of) {
+        (Some(l), Some(r)) if is_multiple_of(l, r) => Some(l),
+        (Some(l), Some(r)) if is_multiple_of(r, l) => Some(r),
+        (Some(_), Some(_)) => return None,
+        (l, r) => l.or(r),
+    };
+    merge_with(&mut target.maximum, source.maximum, f64::min);
+    merge_with(
+        &mut target.exclusive_maximum,
+        source.exclusive_maximum,
+        f64::min,
+    );
+    merge_with(&mut target.minimum, source.minimum, f64::max);
+    merge_with(
+        &mut target.exclusive_minimum,
+        source.exclusive_minimum,
+        f64::max,
+    );
+    Some(())
+}
+
+fn merge_string(target: &mut StringValidation, source: StringValidation) -> Option<()> {
+    merge_with(&mut target.max_length, source.max_length, u32::min);
+    merge_with(&mut target.min_length, source.min_length, u32::max);
+    merge_eq(&mut target.pattern, source.pattern)?;
+    Some(())
+}
+
+fn merge_array(target: &mut ArrayValidation, source: ArrayValidation) -> Option<()> {
+    merge_eq(&mut target.items, source.items)?;
+    merge_eq(&mut target.additional_items, source.additional_items)?;
+    merge_with(&mut target.max_items, source.max_items, u32::min);
+    merge_with(&mut target.min_items, source.min_items, u32::max);
+    merge_with(&mut target.unique_items, source.unique_items, |l, r| l || r);
+    merge_eq(&mut target.contains, source.contains)?;
+    Some(())
+}
+
+fn merge_object(target: &mut ObjectValidation, source: ObjectValidation) -> Option<()> {
+    // additionalProperties only applies to properties declared next to it, so a branch that
+    // restricts it can't be merged with a branch that declares other properties.
+    if closes_over(target, &source) || closes_over(&source, target) {
+        return None;
+    }
+
+    merge_with(&mut target.max_properties, source.max_properties, u32::min);
+    merge_with(&mut target.min_properties, source.min_properties, u32::max);
+    target.required.extend(source.required);
+    for (key, schema) in source.properties {
+        merge_property(&mut target.properties, key, schema);
+    }
+    for (key, schema) in source.pattern_properties {
+        merge_property(&mut target.pattern_properties, key, schema);
+    }
+    merge_eq(
+        &mut target.additional_properties,
+        source.additional_properties,
+    )?;
+    merge_eq(&mut target.property_names, source.property_names)?;
+    Some(())
+}
+
+/// Whether `lhs` forbids properties that `rhs` declares.
+fn closes_over(lhs: &ObjectValidation, rhs: &ObjectValidation) -> bool {
+    let is_open = lhs
+        .additional_properties
+        .as_deref()
+        .is_none_or(|x| *x == Schema::Bool(true));
+    !is_open
+        && (rhs
+            .properties
+            .keys()
+            .any(|key| !lhs.properties.contains_key(key))
+            || rhs
+                .pattern_properties
+                .keys()
+                .any(|key| !lhs.pattern_properties.contains_key(key)))
+}
+
+/// A property declared in two branches has to satisfy both schemas. Nest another `allOf` for it,
+/// which gets merged once the walker gets to that property.
+fn merge_property(properties: &mut schemars::Map<String, Schema>, key: String, schema: Schema) {
+    match properties.get_mut(&key) {
+        Some(existing) if *existing == schema => (),
+        Some(existing) => {
+            *existing = Schema::Object(SchemaObject {
+                subschemas: Some(Box::new(SubschemaValidation {
+                    all_of: Some(vec![existing.clone(), schema]),
+                    ..Default::default()
+                })),
+                ..Default::default()
+            });
+        }
+        None => {
+            properties.insert(key, schema);
+        }
+    }
+}
+
+fn intersect_types(
+    lhs: SingleOrVec<InstanceType>,
+    rhs: SingleOrVec<InstanceType>,
+) -> Option<SingleOrVec<InstanceType>> {
+    let into_vec = |types| match types {
+        SingleOrVec::Single(ty) => vec![*ty],
+        SingleOrVec::Vec(tys) => tys,
+    };
+    let rhs = into_vec(rhs);
+
+    let mut intersection = BTreeSet::new();
+    for l in into_vec(lhs) {
+        for &r in &rhs {
+            match (l, r) {
+                _ if l == r => intersection.insert(l),
+                (InstanceType::Number, InstanceType::Integer)
+                | (InstanceType::Integer, InstanceType::Number) => {
+                    intersection.insert(InstanceType::Integer)
+                }
+                _ => false,
+            };
+        }
+    }
+
+    let mut intersection: Vec<_> = intersection.into_iter().collect();
+    match intersection.len() {
+        0 => None,
+        1 => Some(SingleOrVec::Single(Box::new(intersection.remove(0)))),
+        _ => Some(SingleOrVec::Vec(intersection)),
+    }
+}
+
+fn merge_boxed<T>(
+    target: &mut Option<Box<T>>,
+    source: Option<Box<T>>,
+    merge: fn(&mut T, T) -> Option<()>,
+) -> Option<()> {
+    match (target.as_mut(), source) {
+        (_, None) => (),
+        (None, source) => *target = source,
+        (Some(target), Some(source)) => merge(target, *source)?,
+    }
+    Some(())
+}
+
+fn merge_with<T>(target: &mut Option<T>, source: Option<T>, f: impl FnOnce(T, T) -> T) {
+    *target = match (target.take(), source) {
+        (Some(l), Some(r)) => Some(f(l, r)),
+        (l, r) => l.or(r),
+    };
+}
+
+fn merge_eq<T: PartialEq>(target: &mut Option<T>, source: Option<T>) -> Option<()> {
+    match (&*target, source) {
+        (_, None) => (),
+        (None, source) => *target = source,
+        (Some(l), Some(r)) if *l == r => (),
+        _ => return None,
+    }
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merge(value: serde_json::Value) -> serde_json::Value {
+        let schema: SchemaObject = serde_json::from_value(value).unwrap();
+        serde_json::to_value(merge_all_of(&schema, &|_| None)).unwrap()
+    }
+
+    #[test]
+    fn merges_ranges_and_required() {
+        assert_eq!(
+            merge(serde_json::json!({
+                "allOf": [
+                    {"required": ["a"], "properties": {"a": {"minimum": 1}}},
+                    {"required": ["b"], "properties": {"a": {"maximum": 2}}}
+                ]
+            })),
+            serde_json::json!({
+                "required": ["a", "b"],
+                "properties": {
+                    "a": {"allOf": [{"minimum": 1.0}, {"maximum": 2.0}]}
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn intersects_types() {
+        assert_eq!(
+            merge(serde_json::json!({
+                "type": ["number", "string"],
+                "allOf": [{"type": "integer"}]
+            })),
+            serde_json::json!({"type": "integer"})
+        );
+    }
+
+    #[test]
+    fn keeps_conflicting_branches() {
+        assert_eq!(
+            merge(serde_json::json!({
+                "allOf": [{"pattern": "^a"}, {"pattern": "^b"}, {"minLength": 1}]
+            })),
+            serde_json::json!({
+                "minLength": 1,
+                "allOf": [{"pattern": "^a"}, {"pattern": "^b"}]
+            })
+        );
+    }
+
+    #[test]
+    fn keeps_conditionals_apart() {
+        assert_eq!(
+            merge(serde_json::json!({
+                "allOf": [
+                    {"if": {"required": ["a"]}, "then": {"required": ["b"]}},
+                    {"if": {"required": ["b"]}},
+                    {"else": {"required": ["c"]}}
+                ]
+            })),
+            serde_json::json!({
+                "allOf": [
+                    {"if": {"required": ["a"]}, "then": {"required": ["b"]}},
+                    {"if": {"required": ["b"]}}
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn keeps_branches_closed_by_additional_properties() {
+        assert_eq!(
+            merge(serde_json::json!({
+                "allOf": [
+                    {"properties": {"a": {}}, "additionalProperties": false},
+                    {"properties": {"b": {}}}
+                ]
+            })),
+            serde_json::json!({
+                "allOf": [
+                    {"properties": {"a": {}}, "additionalProperties": false},
+                    {"properties": {"b": {}}}
+                ]
+            })
+        );
+    }
+}
@@ -11,6 +11,81 @@ pub struct Change {
     pub path: String,
     /// Data specific to the kind of change.
     pub change: ChangeKind,
+    /// The kind of subschema the change was made in, as far as it affects whether the change is
+    /// breaking.
+    #[serde(skip_serializing_if = "Nesting::is_plain")]
+    pub nesting: Nesting,
+}
+
+impl Change {
+    /// Whether the change is breaking, taking into account where in the schema it was made, see
+    /// `Nesting`.
+    pub fn is_breaking(&self) -> bool {
+        self.is_breaking_when_nested(self.change.is_breaking())
+    }
+
+    /// Whether the change is breaking from the given perspective, taking into account where in
+    /// the schema it was made like `Change::is_breaking`.
+    pub fn is_breaking_for(&self, perspective: Perspective) -> bool {
+        self.is_breaking_when_nested(self.change.is_breaking_for(perspective))
+    }
+
+    fn is_breaking_when_nested(&self, is_breaking: bool) -> bool {
+        if !self.change.affects_validation() {
+            return is_breaking;
+        }
+
+        match self.nesting {
+            Nesting::Plain => is_breaking,
+            Nesting::Negated => !is_breaking,
+            Nesting::Condition => true,
+        }
+    }
+}
+
+/// The kind of subschema a change was made in, as far as it affects whether the change is
+/// breaking.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub enum Nesting {
+    /// Values have to match the subschema, like the root schema or the schema of a property.
+    /// This includes subschemas nested in an even number of `not`.
+    #[default]
+    Plain,
+    /// Values must not match the subschema, because it is nested in an odd number of `not`.
+    /// A change has the opposite effect on what the schema accepts, so
+    /// `ChangeKind::is_breaking` is inverted.
+    Negated,
+    /// The subschema is nested in an `if`. Any change is considered breaking, as it can move
+    /// values from the `else` to the `then` subschema or vice versa.
+    Condition,
+}
+
+impl Nesting {
+    /// The nesting of a subschema that is nested like `inner` within a subschema nested like
+    /// `self`.
+    pub fn nest(self, inner: Nesting) -> Nesting {
+        match (self, inner) {
+            (Nesting::Condition, _) | (_, Nesting::Condition) => Nesting::Condition,
+            (Nesting::Plain, nesting) | (nesting, Nesting::Plain) => nesting,
+            (Nesting::Negated, Nesting::Negated) => Nesting::Plain,
+        }
+    }
+
+    fn is_plain(&self) -> bool {
+        *self == Nesting::Plain
+    }
+}
+
+/// Whose point of view a change is judged from, see `Change::is_breaking_for`.
+///
+/// Besides readOnly/writeOnly, this affects changes to default values if they are judged breaking
+/// for consumers, see `Config::defaults_break_consumers`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Perspective {
+    /// Those that write values validated by the schema, such as the senders of requests.
+    Producer,
+    /// Those that read values validated by the schema, such as the receivers of responses.
+    Consumer,
 }
 
 /// The kind of change + data relevant to the change.
@@ -39,7 +114,11 @@ pub enum ChangeKind {
     /// A property has been added and (depending on additionalProperties) is now additionally
     /// allowed.
     PropertyAdd {
-        /// The value of additionalProperties within the current JSON object.
+        /// The value of additionalProperties within the current JSON object, or whether the
+        /// patternProperties matching the property name allow any value. If the property used to
+        /// be validated by a schema, whether that schema allowed values that the new property
+        /// doesn't; the differences are emitted at the property's path. Patterns that the regex
+        /// crate can't compile, e.g. with lookaround, might match and are judged like `true`.
         lhs_additional_properties: bool,
         /// The name of the added property.
         added: String,
@@ -47,11 +126,47 @@ pub enum ChangeKind {
     /// A property has been removed and (depending on additionalProperties) might now no longer be
     /// allowed.
     PropertyRemove {
-        /// The value of additionalProperties within the current JSON object.
+        /// The value of additionalProperties (or unevaluatedProperties) that the property now
+        /// falls through to, or whether the patternProperties matching the property name allow
+        /// any value. If the property is now validated by a schema other than `true` or `false`,
+        /// whether that schema allows all values of the removed property;
+        /// the differences are emitted at the property's path. `false` if it's unknown what
+        /// validates the property now, e.g. because a subschema evaluates it.
         lhs_additional_properties: bool,
         /// The name of the added property.
         removed: String,
     },
+    /// additionalProperties has been added or changed such that some properties that are not
+    /// declared explicitly are no longer allowed.
+    ///
+    /// The changes that cause this are emitted at `.<additionalProperties>`.
+    AdditionalPropertiesRestrict,
+    /// additionalProperties has been removed or changed such that properties that are not
+    /// declared explicitly are allowed more broadly.
+    ///
+    /// The changes that cause this are emitted at `.<additionalProperties>`.
+    AdditionalPropertiesRelax,
+    /// A patternProperties entry has been added and (depending on additionalProperties) property
+    /// names matching it are now constrained differently.
+    PatternPropertyAdd {
+        /// Whether the new patternProperties entry doesn't allow all values that the schema
+        /// which used to validate matching properties (additionalProperties) allowed. If that
+        /// schema is not just `true` or `false`, the differences are emitted at
+        /// `.<patternProperties:REGEX>`.
+        restricts: bool,
+        /// The regex of the added entry.
+        added: String,
+    },
+    /// A patternProperties entry has been removed and (depending on additionalProperties)
+    /// property names matching it might now no longer be allowed.
+    PatternPropertyRemove {
+        /// Whether the schema that now validates matching properties (additionalProperties)
+        /// doesn't allow all values of the removed patternProperties entry. If that schema is not
+        /// just `true` or `false`, the differences are emitted at `.<patternProperties:REGEX>`.
+        restricts: bool,
+        /// The regex of the removed entry.
+        removed: String,
+    },
     /// A minimum/maximum constraint has been added.
     RangeAdd {
         /// The value of the added constraint.
@@ -69,6 +184,23 @@ pub enum ChangeKind {
         /// The new constraint value.
         new_value: Range,
     },
+    /// A multipleOf constraint has been added.
+    MultipleOfAdd {
+        /// The multipleOf value that was added.
+        added: f64,
+    },
+    /// A multipleOf constraint has been removed.
+    MultipleOfRemove {
+        /// The multipleOf value that was removed.
+        removed: f64,
+    },
+    /// A multipleOf constraint has been changed.
+    MultipleOfChange {
+        /// The old multipleOf value.
+        old_value: f64,
+        /// The new multipleOf value.
+        new_value: f64,
+    },
     /// An array-type item has been changed from tuple validation to array validation.
     ///
     /// See https://json-schema.org/understanding-json-schema/reference/array.html
@@ -92,11 +224,72 @@ pub enum ChangeKind {
     ///
     /// See https://json-schema.org/understanding-json-schema/reference/array.html
     ///
-    /// Changes will still be emitted for inner items.
+    /// Changes will still be emitted for inner items. Items that only exist on one side are
+    /// compared with the other side's additionalItems.
     TupleChange {
         /// The new length of the tuple
         new_length: usize,
     },
+    /// An `anyOf` has been added, so values have to match one of its variants.
+    ///
+    /// The variants are compared against `true` at `.<anyOf:N>`. An `anyOf` with a single variant
+    /// is compared like an `allOf` instead.
+    AnyOfAdd,
+    /// An `anyOf` has been removed.
+    ///
+    /// The variants are compared against `true` at `.<anyOf:N>`.
+    AnyOfRemove,
+    /// A `oneOf` has been added, so values have to match exactly one of its variants.
+    ///
+    /// The variants are compared against `true` at `.<oneOf:N>`. A `oneOf` with a single variant
+    /// is compared like an `allOf` instead.
+    OneOfAdd,
+    /// A `oneOf` has been removed.
+    ///
+    /// The variants are compared against `true` at `.<oneOf:N>`.
+    OneOfRemove,
+    /// An `anyOf` has been changed to a `oneOf` whose variants may overlap.
+    ///
+    /// Values that match more than one variant are no longer valid.
+    ///
+    /// Changes will still be emitted for inner items.
+    AnyOfToOneOf,
+    /// A `oneOf` whose variants overlapped has been changed to an `anyOf`.
+    ///
+    /// Values that match more than one variant are now valid.
+    ///
+    /// Changes will still be emitted for inner items.
+    OneOfToAnyOf,
+    /// Two variants of a `oneOf` may now be matched by the same value, so that value is no longer
+    /// valid.
+    OneOfOverlapAdd {
+        /// The indices of the overlapping variants in the new `oneOf`.
+        variants: (usize, usize),
+    },
+    /// Two variants of a `oneOf` that could be matched by the same value can no longer be matched
+    /// by the same value.
+    OneOfOverlapRemove {
+        /// The indices of the previously overlapping variants in the new `oneOf`.
+        variants: (usize, usize),
+    },
+    /// A `not` subschema has been added, so values matching it are no longer allowed.
+    NotAdd,
+    /// A `not` subschema has been removed, so values matching it are now allowed.
+    NotRemove,
+    /// An `if` subschema has been added, together with a `then` or `else` subschema that now
+    /// constrains values depending on it.
+    ConditionalAdd,
+    /// An `if` subschema has been removed, together with the `then` and `else` subschemas that
+    /// constrained values depending on it.
+    ConditionalRemove,
+    /// A `then` subschema has been added to an existing `if`.
+    ThenAdd,
+    /// A `then` subschema has been removed from an existing `if`.
+    ThenRemove,
+    /// An `else` subschema has been added to an existing `if`.
+    ElseAdd,
+    /// An `else` subschema has been removed from an existing `if`.
+    ElseRemove,
     /// A previously required property has been removed
     RequiredRemove {
         /// The property that is no longer required
@@ -107,6 +300,32 @@ pub enum ChangeKind {
         /// The property that is now required
         property: String,
     },
+    /// A property is now required whenever another property is present.
+    DependentRequiredAdd {
+        /// The property whose presence triggers the requirement.
+        property: String,
+        /// The property that is now required.
+        dependent: String,
+    },
+    /// A property is no longer required whenever another property is present.
+    DependentRequiredRemove {
+        /// The property whose presence triggered the requirement.
+        property: String,
+        /// The property that is no longer required.
+        dependent: String,
+    },
+    /// A subschema now applies whenever a property is present.
+    ///
+    /// Changes to existing dependent schemas are emitted at `.<dependentSchemas:PROPERTY>`.
+    DependentSchemaAdd {
+        /// The property whose presence triggers the subschema.
+        property: String,
+    },
+    /// A subschema no longer applies whenever a property is present.
+    DependentSchemaRemove {
+        /// The property whose presence triggered the subschema.
+        property: String,
+    },
     /// A format constraint has been added.
     FormatAdd {
         /// The format that was added.
@@ -124,7 +343,121 @@ pub enum ChangeKind {
         /// The new format value.
         new_format: String,
     },
+    /// A property has been marked as readOnly, so producers should no longer write it.
+    ReadOnlyAdd,
+    /// A property is no longer marked as readOnly.
+    ReadOnlyRemove,
+    /// A property has been marked as writeOnly, so consumers can no longer expect to read it.
+    WriteOnlyAdd,
+    /// A property is no longer marked as writeOnly.
+    WriteOnlyRemove,
+    /// A schema has been marked as deprecated. This does not affect validation.
+    DeprecationAdd,
+    /// A schema is no longer marked as deprecated. This does not affect validation.
+    DeprecationRemove,
+    /// A default value has been added, so consumers fill in a missing value.
+    DefaultAdd {
+        /// The added default value.
+        added: serde_json::Value,
+        /// Whether this is breaking for consumers, see `Config::defaults_break_consumers`.
+        breaks_consumers: bool,
+    },
+    /// The default value has been removed, so consumers no longer fill in a missing value.
+    DefaultRemove {
+        /// The removed default value.
+        removed: serde_json::Value,
+        /// Whether this is breaking for consumers, see `Config::defaults_break_consumers`.
+        breaks_consumers: bool,
+    },
+    /// The default value has been changed, so consumers fill in a different missing value.
+    DefaultChange {
+        /// The old default value.
+        old_value: serde_json::Value,
+        /// The new default value.
+        new_value: serde_json::Value,
+        /// Whether this is breaking for consumers, see `Config::defaults_break_consumers`.
+        breaks_consumers: bool,
+    },
+    /// The new default value does not validate against the type, enum or const of its schema.
+    DefaultInvalid {
+        /// The invalid default value.
+        value: serde_json::Value,
+    },
+    /// The title has been added, removed or changed. Only reported with
+    /// `Config::diff_annotations`.
+    TitleChange {
+        /// The old title.
+        old_value: Option<String>,
+        /// The new title.
+        new_value: Option<String>,
+    },
+    /// The description has been added, removed or changed. Only reported with
+    /// `Config::diff_annotations`.
+    DescriptionChange {
+        /// The old description.
+        old_value: Option<String>,
+        /// The new description.
+        new_value: Option<String>,
+    },
+    /// The examples have been changed. Only reported with `Config::diff_annotations`.
+    ExamplesChange {
+        /// The old examples.
+        old_value: Vec<serde_json::Value>,
+        /// The new examples.
+        new_value: Vec<serde_json::Value>,
+    },
+    /// The `$comment` has been added, removed or changed. Only reported with
+    /// `Config::diff_annotations`.
+    CommentChange {
+        /// The old comment.
+        old_value: Option<String>,
+        /// The new comment.
+        new_value: Option<String>,
+    },
+    /// A contentEncoding has been added, so string values must now be encoded with it.
+    ContentEncodingAdd {
+        /// The encoding that was added.
+        added: String,
+    },
+    /// A contentEncoding has been removed.
+    ContentEncodingRemove {
+        /// The encoding that was removed.
+        removed: String,
+    },
+    /// The contentEncoding has been changed.
+    ContentEncodingChange {
+        /// The old encoding.
+        old_encoding: String,
+        /// The new encoding.
+        new_encoding: String,
+    },
+    /// A contentMediaType has been added, so string values must now contain a document of that
+    /// media type.
+    ContentMediaTypeAdd {
+        /// The media type that was added.
+        added: String,
+    },
+    /// A contentMediaType has been removed.
+    ContentMediaTypeRemove {
+        /// The media type that was removed.
+        removed: String,
+    },
+    /// The contentMediaType has been changed.
+    ContentMediaTypeChange {
+        /// The old media type.
+        old_media_type: String,
+        /// The new media type.
+        new_media_type: String,
+    },
+    /// A contentSchema has been added, so the embedded document is now constrained by it.
+    ///
+    /// Changes within a contentSchema on both sides are emitted at `.<content>`.
+    ContentSchemaAdd,
+    /// A contentSchema has been removed, so the embedded document is no longer constrained.
+    ContentSchemaRemove,
     /// An enum value has been added to the allowed values.
+    ///
+    /// When the other side has an enum, a const is compared as an enum with a single value.
     EnumAdd {
         /// The value that was added to the enum.
         added: serde_json::Value,
@@ -133,6 +466,8 @@ pub enum ChangeKind {
         lhs_has_no_enum: bool,
     },
     /// An enum value has been removed from the allowed values.
+    ///
+    /// When the other side has an enum, a const is compared as an enum with a single value.
     EnumRemove {
         /// The value that was removed from the enum.
         removed: serde_json::Value,
@@ -191,6 +526,118 @@ pub enum ChangeKind {
         /// The new maxLength value.
         new_value: u32,
     },
+    /// A minItems constraint has been added.
+    MinItemsAdd {
+        /// The minItems value that was added.
+        added: u32,
+    },
+    /// A minItems constraint has been removed.
+    MinItemsRemove {
+        /// The minItems value that was removed.
+        removed: u32,
+    },
+    /// A minItems constraint has been changed.
+    MinItemsChange {
+        /// The old minItems value.
+        old_value: u32,
+        /// The new minItems value.
+        new_value: u32,
+    },
+    /// A maxItems constraint has been added.
+    MaxItemsAdd {
+        /// The maxItems value that was added.
+        added: u32,
+    },
+    /// A maxItems constraint has been removed.
+    MaxItemsRemove {
+        /// The maxItems value that was removed.
+        removed: u32,
+    },
+    /// A maxItems constraint has been changed.
+    MaxItemsChange {
+        /// The old maxItems value.
+        old_value: u32,
+        /// The new maxItems value.
+        new_value: u32,
+    },
+    /// A minProperties constraint has been added.
+    MinPropertiesAdd {
+        /// The minProperties value that was added.
+        added: u32,
+    },
+    /// A minProperties constraint has been removed.
+    MinPropertiesRemove {
+        /// The minProperties value that was removed.
+        removed: u32,
+    },
+    /// A minProperties constraint has been changed.
+    MinPropertiesChange {
+        /// The old minProperties value.
+        old_value: u32,
+        /// The new minProperties value.
+        new_value: u32,
+    },
+    /// A maxProperties constraint has been added.
+    MaxPropertiesAdd {
+        /// The maxProperties value that was added.
+        added: u32,
+    },
+    /// A maxProperties constraint has been removed.
+    MaxPropertiesRemove {
+        /// The maxProperties value that was removed.
+        removed: u32,
+    },
+    /// A maxProperties constraint has been changed.
+    MaxPropertiesChange {
+        /// The old maxProperties value.
+        old_value: u32,
+        /// The new maxProperties value.
+        new_value: u32,
+    },
+    /// A contains constraint has been added, so arrays now need to contain a matching item.
+    ///
+    /// Changes to an existing contains constraint are emitted at `.<contains>`.
+    ContainsAdd,
+    /// A contains constraint has been removed.
+    ContainsRemove,
+    /// A minContains constraint has been added.
+    MinContainsAdd {
+        /// The minContains value that was added.
+        added: u32,
+    },
+    /// A minContains constraint has been removed.
+    MinContainsRemove {
+        /// The minContains value that was removed.
+        removed: u32,
+    },
+    /// A minContains constraint has been changed.
+    MinContainsChange {
+        /// The old minContains value.
+        old_value: u32,
+        /// The new minContains value.
+        new_value: u32,
+    },
+    /// A maxContains constraint has been added.
+    MaxContainsAdd {
+        /// The maxContains value that was added.
+        added: u32,
+    },
+    /// A maxContains constraint has been removed.
+    MaxContainsRemove {
+        /// The maxContains value that was removed.
+        removed: u32,
+    },
+    /// A maxContains constraint has been changed.
+    MaxContainsChange {
+        /// The old maxContains value.
+        old_value: u32,
+        /// The new maxContains value.
+        new_value: u32,
+    },
+    /// Array items are now required to be unique.
+    UniqueItemsAdd,
+    /// Array items are no longer required to be unique.
+    UniqueItemsRemove,
 }
 
 impl ChangeKind {
@@ -201,6 +648,12 @@ impl ChangeKind {
     ///
     /// Currently the rule of thumb is, a change is breaking if it would cause messages that used
     /// to validate fine under RHS to no longer validate under LHS.
+    ///
+    /// Changes that only affect either producers or consumers, such as `ReadOnlyAdd`, are
+    /// considered breaking if they break either, see `ChangeKind::is_breaking_for`. Changes to
+    /// default values are only considered breaking from the perspective of consumers.
+    ///
+    /// This does not know where the change was made, see `Change::is_breaking`.
     pub fn is_breaking(&self) -> bool {
         match self {
             Self::TypeAdd { .. } => false,
@@ -215,6 +668,10 @@ impl ChangeKind {
                 lhs_additional_properties,
                 ..
             } => !*lhs_additional_properties,
+            Self::AdditionalPropertiesRestrict => true,
+            Self::AdditionalPropertiesRelax => false,
+            Self::PatternPropertyAdd { restricts, .. } => *restricts,
+            Self::PatternPropertyRemove { restricts, .. } => *restricts,
             Self::RangeAdd { .. } => true,
             Self::RangeRemove { .. } => false,
             Self::RangeChange {
@@ -229,14 +686,72 @@ impl ChangeKind {
                 (Range::ExclusiveMaximum(l), Range::ExclusiveMaximum(r)) if l <= r => false,
                 _ => true,
             },
+            Self::MultipleOfAdd { .. } => true,
+            Self::MultipleOfRemove { .. } => false,
+            // Every multiple of the old value is still valid if the old value is itself a
+            // multiple of the new value, e.g. going from 4 to 2.
+            Self::MultipleOfChange {
+                old_value,
+                new_value,
+            } => !is_multiple_of(*old_value, *new_value),
+            // Whether these are breaking depends on the items and additionalItems, so it is
+            // reported by the diffs of the items at each position.
             Self::TupleToArray { .. } => false,
-            Self::ArrayToTuple { .. } => true,
-            Self::TupleChange { .. } => true,
+            Self::ArrayToTuple { .. } => false,
+            Self::TupleChange { .. } => false,
+            Self::NotAdd => true,
+            Self::NotRemove => false,
+            Self::ConditionalAdd => true,
+            Self::ConditionalRemove => false,
+            Self::ThenAdd => true,
+            Self::ThenRemove => false,
+            Self::ElseAdd => true,
+            Self::ElseRemove => false,
+            Self::AnyOfAdd => true,
+            Self::AnyOfRemove => false,
+            Self::OneOfAdd => true,
+            Self::OneOfRemove => false,
+            Self::AnyOfToOneOf => true,
+            Self::OneOfToAnyOf => false,
+            Self::OneOfOverlapAdd { .. } => true,
+            Self::OneOfOverlapRemove { .. } => false,
             Self::RequiredRemove { .. } => false,
             Self::RequiredAdd { .. } => true,
+            Self::DependentRequiredAdd { .. } => true,
+            Self::DependentRequiredRemove { .. } => false,
+            Self::DependentSchemaAdd { .. } => true,
+            Self::DependentSchemaRemove { .. } => false,
             Self::FormatAdd { .. } => true,
             Self::FormatRemove { .. } => false,
             Self::FormatChange { .. } => true,
+            Self::ReadOnlyAdd => true,
+            Self::ReadOnlyRemove => false,
+            Self::WriteOnlyAdd => true,
+            Self::WriteOnlyRemove => false,
+            Self::DeprecationAdd => false,
+            Self::DeprecationRemove => false,
+            Self::DefaultAdd {
+                breaks_consumers, ..
+            }
+            | Self::DefaultRemove {
+                breaks_consumers, ..
+            }
+            | Self::DefaultChange {
+                breaks_consumers, ..
+            } => *breaks_consumers,
+            Self::DefaultInvalid { .. } => true,
+            Self::TitleChange { .. }
+            | Self::DescriptionChange { .. }
+            | Self::ExamplesChange { .. }
+            | Self::CommentChange { .. } => false,
+            Self::ContentEncodingAdd { .. } => true,
+            Self::ContentEncodingRemove { .. } => false,
+            Self::ContentEncodingChange { .. } => true,
+            Self::ContentMediaTypeAdd { .. } => true,
+            Self::ContentMediaTypeRemove { .. } => false,
+            Self::ContentMediaTypeChange { .. } => true,
+            Self::ContentSchemaAdd => true,
+            Self::ContentSchemaRemove => false,
             // EnumAdd is breaking only if it adds a new enum constraint (lhs had no enum).
             // Adding values to an existing enum is non-breaking (accepts more data).
             Self::EnumAdd {
@@ -266,6 +781,178 @@ impl ChangeKind {
                 old_value,
                 new_value,
             } => new_value < old_value,
+            // MinItems: increasing restricts (breaking), decreasing relaxes (non-breaking)
+            Self::MinItemsAdd { .. } => true,
+            Self::MinItemsRemove { .. } => false,
+            Self::MinItemsChange {
+                old_value,
+                new_value,
+            } => new_value > old_value,
+            // MaxItems: decreasing restricts (breaking), increasing relaxes (non-breaking)
+            Self::MaxItemsAdd { .. } => true,
+            Self::MaxItemsRemove { .. } => false,
+            Self::MaxItemsChange {
+                old_value,
+                new_value,
+            } => new_value < old_value,
+            // MinProperties: increasing restricts (breaking), decreasing relaxes (non-breaking)
+            Self::MinPropertiesAdd { .. } => true,
+            Self::MinPropertiesRemove { .. } => false,
+            Self::MinPropertiesChange {
+                old_value,
+                new_value,
+            } => new_value > old_value,
+            // MaxProperties: decreasing restricts (breaking), increasing relaxes (non-breaking)
+            Self::MaxPropertiesAdd { .. } => true,
+            Self::MaxPropertiesRemove { .. } => false,
+            Self::MaxPropertiesChange {
+                old_value,
+                new_value,
+            } => new_value < old_value,
+            Self::ContainsAdd => true,
+            Self::ContainsRemove => false,
+            // MinContains defaults to 1, so only a change relative to that is breaking.
+            Self::MinContainsAdd { added } => *added > 1,
+            Self::MinContainsRemove { removed } => *removed < 1,
+            Self::MinContainsChange {
+                old_value,
+                new_value,
+            } => new_value > old_value,
+            Self::MaxContainsAdd { .. } => true,
+            Self::MaxContainsRemove { .. } => false,
+            Self::MaxContainsChange {
+                old_value,
+                new_value,
+            } => new_value < old_value,
+            Self::UniqueItemsAdd => true,
+            Self::UniqueItemsRemove => false,
+        }
+    }
+
+    /// Whether the change can make values valid or invalid, as opposed to only changing
+    /// annotations such as `deprecated` or `default`.
+    pub(crate) fn affects_validation(&self) -> bool {
+        !self.is_annotation()
+            && !matches!(
+                self,
+                Self::ReadOnlyAdd
+                    | Self::ReadOnlyRemove
+                    | Self::WriteOnlyAdd
+                    | Self::WriteOnlyRemove
+                    | Self::DeprecationAdd
+                    | Self::DeprecationRemove
+                    | Self::DefaultAdd { .. }
+                    | Self::DefaultRemove { .. }
+                    | Self::DefaultChange { .. }
+                    | Self::DefaultInvalid { .. }
+            )
+    }
+
+    /// Whether the change only concerns documentation, which doesn't affect validation.
+    ///
+    /// Such changes are reported separately from any validation change at the same path.
+    pub fn is_annotation(&self) -> bool {
+        matches!(
+            self,
+            Self::TitleChange { .. }
+                | Self::DescriptionChange { .. }
+                | Self::ExamplesChange { .. }
+                | Self::CommentChange { .. }
+        )
+    }
+
+    /// Whether the change is breaking for either producers or consumers of the values that are
+    /// validated by the schema.
+    ///
+    /// This only differs from `ChangeKind::is_breaking` for changes that affect one of them, such
+    /// as `ReadOnlyAdd`, which breaks producers that still write the property.
+    pub fn is_breaking_for(&self, perspective: Perspective) -> bool {
+        match (self, perspective) {
+            (Self::ReadOnlyAdd, Perspective::Producer) => true,
+            (Self::ReadOnlyAdd, Perspective::Consumer) => false,
+            (Self::WriteOnlyAdd, Perspective::Producer) => false,
+            (Self::WriteOnlyAdd, Perspective::Consumer) => true,
+            // Only consumers fill in missing values.
+            (
+                Self::DefaultAdd { .. } | Self::DefaultRemove { .. } | Self::DefaultChange { .. },
+                Perspective::Producer,
+            ) => false,
+            _ => self.is_breaking(),
+        }
+    }
+}
+
+/// Whether `value` is an integer multiple of `divisor`.
+///
+/// Tolerates floating-point error, so that e.g. `0.3` is considered a multiple of `0.1`.
+pub(crate) fn is_multiple_of(value: f64, divisor: f64) -> bool {
+    if divisor == 0.0 {
+        return false;
+    }
+    let quotient = value / divisor;
+    (quotient - quotient.round()).abs() <= 1e-9 * quotient.abs().max(1.0)
+}
+
+/// Whether two JSON values are equal in the sense of JSON Schema: numbers are compared by their
+/// mathematical value (so `1` equals `1.0`) and objects regardless of key order.
+pub(crate) fn values_equal(lhs: &serde_json::Value, rhs: &serde_json::Value) -> bool {
+    use serde_json::Value;
+
+    match (lhs, rhs) {
+        (Value::Number(l), Value::Number(r)) => {
+            l == r || ((l.is_f64() || r.is_f64()) && l.as_f64() == r.as_f64())
+        }
+        (Value::Array(l), Value::Array(r)) => {
+            l.len() == r.len() && l.iter().zip(r).all(|(l, r)| values_equal(l, r))
+        }
+        (Value::Object(l), Value::Object(r)) => {
+            l.len() == r.len()
+                && l.iter()
+                    .all(|(key, l)| r.get(key).is_some_and(|r| values_equal(l, r)))
+        }
+        _ => lhs == rhs,
+    }
+}
+
+/// Options for comparing two schemas.
+///
+/// These mostly decide which changes are reported. Whether a change is breaking is judged
+/// afterwards, see `Change::is_breaking_for` and `Perspective`, except for changes to default
+/// values, which record `Config::defaults_break_consumers`.
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    /// The draft to assume for a schema that does not declare one using `$schema`.
+    pub default_draft: Draft,
+    /// Don't report `DeprecationAdd`/`DeprecationRemove`.
+    pub ignore_deprecations: bool,
+    /// Also report changes to `title`, `description`, `examples` and `$comment`, see
+    /// `ChangeKind::is_annotation`.
+    pub diff_annotations: bool,
+    /// Judge adding, removing or changing a default value as breaking for consumers, which fill
+    /// in missing values with it. It is never breaking for producers.
+    pub defaults_break_consumers: bool,
+}
+
+/// The JSON schema drafts that are treated differently.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Draft {
+    /// Draft 2019-09, draft 7 and all drafts before them, where `items` and `additionalItems`
+    /// describe tuples.
+    #[default]
+    Draft7,
+    /// Draft 2020-12, where `prefixItems` and `items` replace `items` and `additionalItems`.
+    Draft2020_12,
+}
+
+impl Draft {
+    /// Determine the draft from the value of `$schema`.
+    pub fn from_meta_schema(uri: &str) -> Option<Self> {
+        if uri.contains("/draft/2020-12/") {
+            Some(Draft::Draft2020_12)
+        } else if uri.contains("/draft/2019-09/") || uri.contains("/draft-0") {
+            Some(Draft::Draft7)
+        } else {
+            None
         }
     }
 }
@@ -343,6 +1030,218 @@ pub enum Range {
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    #[test]
+    fn is_breaking_within_not() {
+        let change = |nesting| Change {
+            path: ".foo".to_owned(),
+            change: ChangeKind::TypeRemove {
+                removed: JsonSchemaType::String,
+            },
+            nesting,
+        };
+        assert!(change(Nesting::Plain).is_breaking());
+        assert!(!change(Nesting::Negated).is_breaking());
+        assert!(change(Nesting::Negated.nest(Nesting::Negated)).is_breaking());
+        assert!(!change(Nesting::Plain.nest(Nesting::Negated)).is_breaking());
+    }
+
+    #[test]
+    fn is_breaking_within_if() {
+        let change = |nesting| Change {
+            path: ".foo".to_owned(),
+            change: ChangeKind::TypeAdd {
+                added: JsonSchemaType::String,
+            },
+            nesting,
+        };
+        assert!(!change(Nesting::Plain).is_breaking());
+        assert!(change(Nesting::Condition).is_breaking());
+        assert!(change(Nesting::Negated.nest(Nesting::Condition)).is_breaking());
+        assert!(change(Nesting::Condition.nest(Nesting::Negated)).is_breaking());
+    }
+
+    #[test]
+    fn affects_validation() {
+        let not_validating = || {
+            vec![
+                ChangeKind::ReadOnlyAdd,
+                ChangeKind::ReadOnlyRemove,
+                ChangeKind::WriteOnlyAdd,
+                ChangeKind::WriteOnlyRemove,
+                ChangeKind::DeprecationAdd,
+                ChangeKind::DeprecationRemove,
+                ChangeKind::DefaultAdd {
+                    added: 1.into(),
+                    breaks_consumers: true,
+                },
+                ChangeKind::DefaultRemove {
+                    removed: 1.into(),
+                    breaks_consumers: false,
+                },
+                ChangeKind::DefaultChange {
+                    old_value: 1.into(),
+                    new_value: 2.into(),
+                    breaks_consumers: true,
+                },
+                ChangeKind::DefaultInvalid { value: 1.into() },
+                ChangeKind::TitleChange {
+                    old_value: None,
+                    new_value: Some("Foo".to_owned()),
+                },
+                ChangeKind::DescriptionChange {
+                    old_value: Some("Foo".to_owned()),
+                    new_value: None,
+                },
+                ChangeKind::ExamplesChange {
+                    old_value: vec![],
+                    new_value: vec![1.into()],
+                },
+                ChangeKind::CommentChange {
+                    old_value: Some("Foo".to_owned()),
+                    new_value: Some("Bar".to_owned()),
+                },
+            ]
+        };
+        assert!(not_validating()
+            .iter()
+            .all(|change| !change.affects_validation()));
+        assert!(ChangeKind::TypeRemove {
+            removed: JsonSchemaType::String,
+        }
+        .affects_validation());
+
+        // Whether these are breaking doesn't depend on the subschema they are nested in.
+        for nesting in [Nesting::Negated, Nesting::Condition] {
+            for change in not_validating() {
+                let is_breaking = change.is_breaking();
+                let change = Change {
+                    path: ".foo".to_owned(),
+                    change,
+                    nesting,
+                };
+                assert_eq!(change.is_breaking(), is_breaking, "{change:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn draft_from_meta_schema() {
+        assert_eq!(
+            Draft::from_meta_schema("http://json-schema.org/draft-07/schema#"),
+            Some(Draft::Draft7)
+        );
+        assert_eq!(
+            Draft::from_meta_schema("https://json-schema.org/draft/2019-09/schema"),
+            Some(Draft::Draft7)
+        );
+        assert_eq!(
+            Draft::from_meta_schema("https://json-schema.org/draft/2020-12/schema"),
+            Some(Draft::Draft2020_12)
+        );
+        assert_eq!(Draft::from_meta_schema("urn:example:custom"), None);
+    }
+
+    #[test]
+    fn is_breaking_for_perspective() {
+        let change = |change| Change {
+            path: ".id".to_owned(),
+            change,
+            nesting: Nesting::Plain,
+        };
+
+        assert!(change(ChangeKind::ReadOnlyAdd).is_breaking_for(Perspective::Producer));
+        assert!(!change(ChangeKind::ReadOnlyAdd).is_breaking_for(Perspective::Consumer));
+        assert!(!change(ChangeKind::WriteOnlyAdd).is_breaking_for(Perspective::Producer));
+        assert!(change(ChangeKind::WriteOnlyAdd).is_breaking_for(Perspective::Consumer));
+        assert!(change(ChangeKind::ReadOnlyAdd).is_breaking());
+        assert!(!change(ChangeKind::ReadOnlyRemove).is_breaking_for(Perspective::Producer));
+
+        let default_change = |breaks_consumers| {
+            change(ChangeKind::DefaultChange {
+                old_value: 1.into(),
+                new_value: 2.into(),
+                breaks_consumers,
+            })
+        };
+        assert!(!default_change(false).is_breaking());
+        assert!(!default_change(false).is_breaking_for(Perspective::Producer));
+        assert!(!default_change(false).is_breaking_for(Perspective::Consumer));
+        assert!(default_change(true).is_breaking());
+        assert!(!default_change(true).is_breaking_for(Perspective::Producer));
+        assert!(default_change(true).is_breaking_for(Perspective::Consumer));
+
+        let type_remove = change(ChangeKind::TypeRemove {
+            removed: JsonSchemaType::String,
+        });
+        assert!(type_remove.is_breaking_for(Perspective::Producer));
+        assert!(type_remove.is_breaking_for(Perspective::Consumer));
+    }
+
+    #[test]
+    fn values_equal_numerically() {
+        use serde_json::json;
+
+        assert!(values_equal(&json!(1), &json!(1.0)));
+        assert!(values_equal(&json!(-2.0), &json!(-2)));
+        assert!(!values_equal(&json!(1), &json!(1.5)));
+        assert!(!values_equal(&json!(1), &json!("1")));
+        assert!(values_equal(
+            &json!({"a": [1, {"b": 2.0}], "c": null}),
+            &json!({"c": null, "a": [1.0, {"b": 2}]})
+        ));
+        assert!(!values_equal(&json!({"a": 1}), &json!({"a": 1, "b": 2})));
+        assert!(!values_equal(&json!([1, 2]), &json!([2, 1])));
+    }
+
+    #[test]
+    fn is_multiple_of_change_breaking() {
+        assert!(ChangeKind::MultipleOfChange {
+            old_value: 2.0,
+            new_value: 4.0,
+        }
+        .is_breaking());
+
+        assert!(!ChangeKind::MultipleOfChange {
+            old_value: 4.0,
+            new_value: 2.0,
+        }
+        .is_breaking());
+
+        assert!(ChangeKind::MultipleOfChange {
+            old_value: 2.0,
+            new_value: 3.0,
+        }
+        .is_breaking());
+
+        assert!(!ChangeKind::MultipleOfChange {
+            old_value: 0.3,
+            new_value: 0.1,
+        }
+        .is_breaking());
+
+        assert!(ChangeKind::MultipleOfChange {
+            old_value: 0.1,
+            new_value: 0.3,
+        }
+        .is_breaking());
+
+        assert!(!ChangeKind::MultipleOfChange {
+            old_value: 1.0,
+            new_value: 0.01,
+        }
+        .is_breaking());
+    }
+
+    #[test]
+    fn is_min_contains_change_breaking() {
+        assert!(!ChangeKind::MinContainsAdd { added: 0 }.is_breaking());
+        assert!(!ChangeKind::MinContainsAdd { added: 1 }.is_breaking());
+        assert!(ChangeKind::MinContainsAdd { added: 2 }.is_breaking());
+        assert!(ChangeKind::MinContainsRemove { removed: 0 }.is_breaking());
+        assert!(!ChangeKind::MinContainsRemove { removed: 2 }.is_breaking());
+    }
+
     #[test]
     fn is_range_change_breaking() {
         assert!(!ChangeKind::RangeChange {
@@ -1,6 +1,8 @@
 use insta::{assert_debug_snapshot, glob, with_settings};
-use json_schema_diff::diff;
-use serde_json::Value;
+use json_schema_diff::{
+    diff, diff_with_config, Change, ChangeKind, Config, Draft, Nesting, Perspective,
+};
+use serde_json::{json, Value};
 
 #[test]
 fn test_from_fixtures() {
@@ -14,3 +16,141 @@ fn test_from_fixtures() {
     };
     glob!("../tests/fixtures", "**/*.json", test);
 }
+
+#[test]
+fn test_default_draft() {
+    let lhs = json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "type": "array",
+        "items": [{"type": "string"}],
+        "additionalItems": false
+    });
+    let rhs = json!({
+        "type": "array",
+        "prefixItems": [{"type": "string"}],
+        "items": false
+    });
+
+    let config = Config {
+        default_draft: Draft::Draft2020_12,
+        ..Default::default()
+    };
+    assert_eq!(
+        diff_with_config(lhs.clone(), rhs.clone(), &config).unwrap(),
+        vec![]
+    );
+    assert_ne!(diff(lhs, rhs).unwrap(), vec![]);
+}
+
+#[test]
+fn test_ignore_deprecations() {
+    let lhs = json!({"properties": {"name": {"type": "string"}}});
+    let rhs = json!({"properties": {"name": {"type": "string", "deprecated": true}}});
+
+    let config = Config {
+        ignore_deprecations: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        diff_with_config(lhs.clone(), rhs.clone(), &config).unwrap(),
+        vec![]
+    );
+    assert_eq!(
+        diff(lhs, rhs).unwrap(),
+        vec![Change {
+            path: ".name".to_owned(),
+            nesting: Nesting::Plain,
+            change: ChangeKind::DeprecationAdd,
+        }]
+    );
+}
+
+#[test]
+fn test_defaults_break_consumers() {
+    let lhs = json!({"properties": {"level": {"type": "string", "default": "info"}}});
+    let rhs = json!({"properties": {"level": {"type": "string", "default": "warn"}}});
+
+    let config = Config {
+        defaults_break_consumers: true,
+        ..Default::default()
+    };
+    let changes = diff_with_config(lhs.clone(), rhs.clone(), &config).unwrap();
+    assert_eq!(changes.len(), 1);
+    assert!(changes[0].is_breaking_for(Perspective::Consumer));
+    assert!(!changes[0].is_breaking_for(Perspective::Producer));
+
+    let changes = diff(lhs, rhs).unwrap();
+    assert_eq!(changes.len(), 1);
+    assert!(!changes[0].is_breaking_for(Perspective::Consumer));
+}
+
+#[test]
+fn test_diff_annotations() {
+    let lhs = json!({
+        "title": "Event",
+        "properties": {
+            "id": {"type": "string", "description": "The ID", "examples": ["a"]}
+        }
+    });
+    let rhs = json!({
+        "title": "Event",
+        "$comment": "Generated",
+        "properties": {
+            "id": {"type": "integer", "description": "The event ID", "examples": ["a"]}
+        }
+    });
+
+    assert!(diff(lhs.clone(), rhs.clone())
+        .unwrap()
+        .iter()
+        .all(|change| !change.change.is_annotation()));
+
+    let config = Config {
+        diff_annotations: true,
+        ..Default::default()
+    };
+    let changes = diff_with_config(lhs, rhs, &config).unwrap();
+    let annotations: Vec<_> = changes
+        .iter()
+        .filter(|change| change.change.is_annotation())
+        .collect();
+    assert_eq!(
+        annotations,
+        vec![
+            &Change {
+                path: "".to_owned(),
+                nesting: Nesting::Plain,
+                change: ChangeKind::CommentChange {
+                    old_value: None,
+                    new_value: Some("Generated".to_owned()),
+                },
+            },
+            &Change {
+                path: ".id".to_owned(),
+                nesting: Nesting::Plain,
+                change: ChangeKind::DescriptionChange {
+                    old_value: Some("The ID".to_owned()),
+                    new_value: Some("The event ID".to_owned()),
+                },
+            },
+        ]
+    );
+    assert!(annotations.iter().all(|change| !change.is_breaking()));
+    assert!(changes.iter().any(|change| change.is_breaking()));
+}
+
+#[test]
+fn test_properties_named_like_keywords() {
+    let lhs = json!({"properties": {"<not>": {"type": "string"}, "<if>": {"type": "string"}}});
+    let rhs = json!({"properties": {"<not>": {"type": "boolean"}, "<if>": {"type": "boolean"}}});
+
+    let changes = diff(lhs, rhs).unwrap();
+    assert_eq!(changes.len(), 4);
+    for change in changes {
+        assert_eq!(change.nesting, Nesting::Plain);
+        assert_eq!(
+            change.is_breaking(),
+            matches!(change.change, ChangeKind::TypeRemove { .. })
+        );
+    }
+}